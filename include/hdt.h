@@ -0,0 +1,97 @@
+/* C interface of the hdt crate, enabled with the capi feature. */
+/* Generated with cbindgen, regenerate with `cbindgen --config cbindgen.toml --output include/hdt.h`. */
+
+#ifndef HDT_H
+#define HDT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Loaded HDT file, opaque to C.
+ */
+typedef struct HdtHandle HdtHandle;
+
+/**
+ * Iterator over the triples of a search, opaque to C.
+ * Keeps the HDT alive, so it stays valid after [`hdt_free`].
+ */
+typedef struct HdtIter HdtIter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Message of the last error on the current thread or NULL if there was none.
+ * Panics in the library, such as failed checksums while loading, are reported as errors as well.
+ * Valid until the next error on the same thread.
+ */
+const char *hdt_last_error(void);
+
+/**
+ * Load the HDT file at the given path.
+ * Returns NULL on error, see [`hdt_last_error`].
+ * # Safety
+ * `path` must be NULL or a valid NUL-terminated string.
+ */
+HdtHandle *hdt_open(const char *path);
+
+/**
+ * Free an HDT returned by [`hdt_open`]. Iterators of the HDT stay valid until they are freed themselves.
+ * # Safety
+ * `hdt` must be NULL or returned by [`hdt_open`] and not freed before.
+ */
+void hdt_free(HdtHandle *hdt);
+
+/**
+ * Search for all triples that fit the given triple pattern, where NULL stands for a variable.
+ * Terms are in the same format as in [`Hdt::triples_with_pattern`].
+ * Returns NULL on error, see [`hdt_last_error`].
+ * # Safety
+ * `hdt` must be returned by [`hdt_open`] and the terms must be NULL or valid NUL-terminated strings.
+ */
+HdtIter *hdt_search(const HdtHandle *hdt, const char *s, const char *p, const char *o);
+
+/**
+ * Advance the iterator and point `s`, `p` and `o` to the terms of the next triple.
+ * The terms stay valid until the next call on the same iterator or [`hdt_iter_free`].
+ * Returns 1 for a triple, 0 at the end and -1 on error, see [`hdt_last_error`].
+ * # Safety
+ * `iter` must be returned by [`hdt_search`] and the term pointers must be NULL or valid for writes.
+ */
+int hdt_iter_next(HdtIter *iter, const char **s, const char **p, const char **o);
+
+/**
+ * Advance the iterator and write the IDs of the next triple to `s`, `p` and `o` without decoding the terms.
+ * Returns 1 for a triple, 0 at the end and -1 on error.
+ * # Safety
+ * `iter` must be returned by [`hdt_search`] and the ID pointers must be NULL or valid for writes.
+ */
+int hdt_iter_next_ids(HdtIter *iter, uint64_t *s, uint64_t *p, uint64_t *o);
+
+/**
+ * Free an iterator returned by [`hdt_search`].
+ * # Safety
+ * `iter` must be NULL or returned by [`hdt_search`] and not freed before.
+ */
+void hdt_iter_free(HdtIter *iter);
+
+/**
+ * Write the number of triples that fit the given triple pattern, where NULL stands for a variable, to `count`,
+ * see [`Hdt::pattern_count`].
+ * Returns 0 on success and -1 on error, see [`hdt_last_error`], so that an error can't be mistaken for an empty result.
+ * # Safety
+ * `hdt` must be returned by [`hdt_open`], the terms must be NULL or valid NUL-terminated strings
+ * and `count` must be valid for writes.
+ */
+int hdt_count(const HdtHandle *hdt, const char *s, const char *p, const char *o, size_t *count);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HDT_H */
@@ -0,0 +1,122 @@
+//! Command line tool to inspect, query and export HDT files.
+use bytesize::ByteSize;
+use clap::{Parser, Subcommand};
+use hdt::export::write_term;
+use hdt::Hdt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "hdt", version, about = "Inspect, query and export HDT files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the header and the sizes of the dictionary and triple sections
+    Info { file: PathBuf },
+    /// Print the triples that fit a pattern like "s p o" as N-Triples, where "?" stands for a variable
+    Search {
+        file: PathBuf,
+        pattern: String,
+        /// Stop after the given number of triples
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+    /// Count the triples that fit a pattern like "s p o", where "?" stands for a variable
+    Count {
+        file: PathBuf,
+        #[arg(default_value = "? ? ?")]
+        pattern: String,
+    },
+    /// Export all triples as N-Triples
+    Dump { file: PathBuf },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        // for example when piped into head
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+        Ok(()) => ExitCode::SUCCESS,
+    }
+}
+
+fn run(command: Command) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    match command {
+        Command::Info { file } => {
+            let hdt = load(&file)?;
+            writeln!(out, "# header")?;
+            for t in &hdt.header.body {
+                let (s, o) = (t.subject.to_hdt_string(), t.object.to_hdt_string());
+                write_triple(&mut out, [s.as_bytes(), t.predicate.as_bytes(), o.as_bytes()])?;
+            }
+            writeln!(out, "# sizes in memory")?;
+            writeln!(out, "total {}", ByteSize(hdt.size_in_bytes() as u64))?;
+            writeln!(out, "shared {:?}", hdt.dict.shared)?;
+            writeln!(out, "subjects {:?}", hdt.dict.subjects)?;
+            writeln!(out, "predicates {:?}", hdt.dict.predicates)?;
+            writeln!(out, "objects {:?}", hdt.dict.objects)?;
+            writeln!(out, "triples {:#?}", hdt.triples)?;
+            writeln!(out, "{} triples", hdt.pattern_count(None, None, None))?;
+        }
+        Command::Search { file, pattern, limit } => {
+            let hdt = load(&file)?;
+            let [s, p, o] = parse_pattern(&pattern);
+            let triples = hdt.triples_with_pattern(s.as_deref(), p.as_deref(), o.as_deref());
+            for (s, p, o) in triples.take(limit.unwrap_or(usize::MAX)) {
+                write_triple(&mut out, [s.as_bytes(), p.as_bytes(), o.as_bytes()])?;
+            }
+        }
+        Command::Count { file, pattern } => {
+            let hdt = load(&file)?;
+            let [s, p, o] = parse_pattern(&pattern);
+            writeln!(out, "{}", hdt.pattern_count(s.as_deref(), p.as_deref(), o.as_deref()))?;
+        }
+        Command::Dump { file } => {
+            load(&file)?.write_ntriples(&mut out)?;
+        }
+    }
+    out.flush()
+}
+
+// triple with terms in dictionary format as an N-Triples line
+fn write_triple(out: &mut impl Write, terms: [&[u8]; 3]) -> io::Result<()> {
+    for term in terms {
+        write_term(out, term)?;
+        out.write_all(b" ")?;
+    }
+    out.write_all(b".\n")
+}
+
+fn load(file: &Path) -> io::Result<Hdt> {
+    let with_path = |e: io::Error| io::Error::new(e.kind(), format!("{}: {e}", file.display()));
+    Hdt::new(BufReader::new(File::open(file).map_err(with_path)?)).map_err(with_path)
+}
+
+/// Split a pattern like `s p o` into terms in dictionary format, where `?` stands for a variable.
+/// The object is the rest of the pattern so that it can be a literal with spaces.
+/// IRIs may be written with or without angle brackets.
+fn parse_pattern(pattern: &str) -> [Option<String>; 3] {
+    let pattern = pattern.trim();
+    let (s, rest) = pattern.split_once(char::is_whitespace).unwrap_or((pattern, ""));
+    let rest = rest.trim_start();
+    let (p, o) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    [s, p, o.trim()].map(|term| {
+        if term.is_empty() || term.starts_with('?') {
+            None
+        } else if let Some(iri) = term.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+            Some(iri.to_owned())
+        } else {
+            Some(term.to_owned())
+        }
+    })
+}
@@ -0,0 +1,348 @@
+//! Builds the dictionary and triples of a new HDT from the terms and triple IDs of existing ones,
+//! which is shared by [`crate::cat`], [`crate::diff`] and [`crate::HdtStore::compact`].
+//! Terms are compared as bytes in a reused buffer, so no `String` is allocated per term.
+use crate::dict_sect_pfc::{DictSectPFCBuilder, DictSectPFCIter};
+use crate::header::Header;
+use crate::triples::{CompactTripleId, Id, SubjectIter, TripleId, TriplesBitmap};
+use crate::{DictSectPFC, FourSectDict, Hdt, IdKind};
+use log::debug;
+use std::collections::BTreeSet;
+
+/// Number of subject IDs of a dictionary plus one, as IDs start at 1.
+pub(crate) const fn subject_len(dict: &FourSectDict) -> usize {
+    dict.shared.num_strings() + dict.subjects.num_strings() + 1
+}
+
+/// Number of predicate IDs of a dictionary plus one, as IDs start at 1.
+pub(crate) const fn predicate_len(dict: &FourSectDict) -> usize {
+    dict.predicates.num_strings() + 1
+}
+
+/// Number of object IDs of a dictionary plus one, as IDs start at 1.
+pub(crate) const fn object_len(dict: &FourSectDict) -> usize {
+    dict.shared.num_strings() + dict.objects.num_strings() + 1
+}
+
+/// Current term of a dictionary section of one of N inputs, which moves forward in ascending order.
+struct Cursor<'a> {
+    iter: DictSectPFCIter<'a>,
+    term: Vec<u8>,
+    // ID of the current term
+    id: Id,
+    done: bool,
+    input: usize,
+    // whether the IDs of the section are subject and object IDs, only the first one is used for predicates
+    roles: [bool; 2],
+}
+
+impl<'a> Cursor<'a> {
+    fn new(sect: &'a DictSectPFC, offset: Id, input: usize, roles: [bool; 2]) -> Self {
+        let mut cursor = Cursor { iter: sect.iter(), term: Vec::new(), id: offset, done: false, input, roles };
+        cursor.advance();
+        cursor
+    }
+
+    fn advance(&mut self) {
+        match self.iter.next_bytes() {
+            Some(bytes) => {
+                self.term.clear();
+                self.term.extend_from_slice(bytes);
+                self.id += 1;
+            }
+            None => self.done = true,
+        }
+    }
+}
+
+/// Calls `f` for each distinct term of the cursors in ascending order with its subject and object ID in each input,
+/// 0 if the input doesn't contain it in that position.
+fn walk<const N: usize>(mut cursors: Vec<Cursor<'_>>, mut f: impl FnMut(&[u8], [[Id; N]; 2])) {
+    debug_assert!(cursors.len() <= 64);
+    loop {
+        let Some(min) = cursors
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.done)
+            .min_by(|(_, x), (_, y)| x.term.cmp(&y.term))
+            .map(|(j, _)| j)
+        else {
+            return;
+        };
+        let mut ids = [[0; N]; 2];
+        let mut matching = 0u64;
+        for (j, c) in cursors.iter().enumerate() {
+            if !c.done && c.term == cursors[min].term {
+                matching |= 1 << j;
+                for (role, &has_role) in c.roles.iter().enumerate() {
+                    if has_role {
+                        ids[role][c.input] = c.id;
+                    }
+                }
+            }
+        }
+        f(&cursors[min].term, ids);
+        for (j, c) in cursors.iter_mut().enumerate() {
+            if matching & (1 << j) != 0 {
+                c.advance();
+            }
+        }
+    }
+}
+
+/// Calls `f` for each subject or object term of the dictionaries in ascending order
+/// with its subject IDs and object IDs in each dictionary, 0 if missing.
+/// The shared section and the subject or object section of a dictionary are disjoint, so each term has one ID per position.
+pub(crate) fn position_terms<const N: usize>(dicts: [&FourSectDict; N], f: impl FnMut(&[u8], [[Id; N]; 2])) {
+    let mut cursors = Vec::with_capacity(3 * N);
+    for (i, d) in dicts.into_iter().enumerate() {
+        let offset = d.shared.num_strings();
+        cursors.push(Cursor::new(&d.shared, 0, i, [true, true]));
+        cursors.push(Cursor::new(&d.subjects, offset, i, [true, false]));
+        cursors.push(Cursor::new(&d.objects, offset, i, [false, true]));
+    }
+    walk(cursors, f);
+}
+
+/// Calls `f` for each predicate term of the dictionaries in ascending order with its ID in each dictionary, 0 if missing.
+pub(crate) fn predicate_terms<const N: usize>(dicts: [&FourSectDict; N], mut f: impl FnMut(&[u8], [Id; N])) {
+    let cursors = dicts.into_iter().enumerate().map(|(i, d)| Cursor::new(&d.predicates, 0, i, [true, false]));
+    walk(cursors.collect(), |term, [ids, _]| f(term, ids));
+}
+
+/// Triple ID type of the buffered triples, chosen up front so that all old and new IDs fit.
+trait Buffered: Copy + Ord {
+    fn pack(t: TripleId) -> Self;
+    fn unpack(self) -> TripleId;
+}
+
+impl Buffered for TripleId {
+    fn pack(t: TripleId) -> Self {
+        t
+    }
+
+    fn unpack(self) -> TripleId {
+        self
+    }
+}
+
+impl Buffered for CompactTripleId {
+    // only used when all IDs are below 2^32, see build
+    fn pack(t: TripleId) -> Self {
+        CompactTripleId {
+            subject_id: t.subject_id as u32,
+            predicate_id: t.predicate_id as u32,
+            object_id: t.object_id as u32,
+        }
+    }
+
+    fn unpack(self) -> TripleId {
+        self.into()
+    }
+}
+
+/// Term IDs of each position of an input that occur in its kept triples.
+struct Used {
+    subjects: Vec<bool>,
+    predicates: Vec<bool>,
+    objects: Vec<bool>,
+}
+
+/// Builds the dictionary sections from the used terms of N inputs.
+/// A term that is used both as a subject and as an object is put in the shared section, which comes first,
+/// so the terms are walked twice to count the shared terms in advance.
+/// Returns the dictionary and the new subject, predicate and object ID for each old ID of each input, 0 if unused.
+fn build_dict<const N: usize>(dicts: [&FourSectDict; N], used: &[Used; N]) -> (FourSectDict, [[Vec<Id>; 3]; N]) {
+    // zero out the IDs of unused terms
+    let filter = |mut ids: [[Id; N]; 2]| {
+        for (i, u) in used.iter().enumerate() {
+            if !u.subjects[ids[0][i]] {
+                ids[0][i] = 0;
+            }
+            if !u.objects[ids[1][i]] {
+                ids[1][i] = 0;
+            }
+        }
+        ids.map(|ids| ids.iter().any(|&id| id != 0).then_some(ids))
+    };
+    let mut shared_len = 0;
+    position_terms(dicts, |_, ids| {
+        if let [Some(_), Some(_)] = filter(ids) {
+            shared_len += 1;
+        }
+    });
+    let new_builder = || DictSectPFCBuilder::new(DictSectPFCBuilder::DEFAULT_BLOCK_SIZE);
+    let (mut shared, mut subjects, mut objects) = (new_builder(), new_builder(), new_builder());
+    let mut maps: [[Vec<Id>; 3]; N] =
+        dicts.map(|d| [subject_len(d), predicate_len(d), object_len(d)].map(|len| vec![0; len]));
+    position_terms(dicts, |term, ids| {
+        let [s, o] = filter(ids);
+        let id = match (s, o) {
+            (Some(_), Some(_)) => {
+                shared.push(term);
+                shared.len()
+            }
+            (Some(_), None) => {
+                subjects.push(term);
+                shared_len + subjects.len()
+            }
+            (None, Some(_)) => {
+                objects.push(term);
+                shared_len + objects.len()
+            }
+            (None, None) => return,
+        };
+        for (pos, old) in [(0, s), (2, o)] {
+            for (i, old_id) in old.unwrap_or([0; N]).into_iter().enumerate() {
+                if old_id != 0 {
+                    maps[i][pos][old_id] = id;
+                }
+            }
+        }
+    });
+    let mut predicates = new_builder();
+    predicate_terms(dicts, |term, ids| {
+        let ids = std::array::from_fn::<_, N, _>(|i| if used[i].predicates[ids[i]] { ids[i] } else { 0 });
+        if ids.iter().all(|&id| id == 0) {
+            return;
+        }
+        predicates.push(term);
+        for (i, old_id) in ids.into_iter().enumerate() {
+            if old_id != 0 {
+                maps[i][1][old_id] = predicates.len();
+            }
+        }
+    });
+    (FourSectDict::new(shared.build(), subjects.build(), predicates.build(), objects.build()), maps)
+}
+
+/// HDT with the triples of N inputs that are kept by the corresponding function, without duplicates.
+/// The dictionary only contains the terms of the kept triples, so a term may move between the shared section
+/// and the subject or object section, and the triple IDs are remapped and sorted in a single pass over each input.
+/// The result has an empty header.
+pub(crate) fn build<const N: usize>(hdts: [&Hdt; N], keep: [&dyn Fn(&TripleId) -> bool; N]) -> Hdt {
+    // new IDs are at most the sum of the old ones, so 32 bit IDs are enough if that fits,
+    // which halves the memory of the buffered triples on 64 bit platforms
+    let max_len: usize =
+        hdts.iter().map(|h| subject_len(&h.dict).max(predicate_len(&h.dict)).max(object_len(&h.dict))).sum();
+    if u32::try_from(max_len).is_ok() {
+        build_with::<N, CompactTripleId>(hdts, keep)
+    } else {
+        build_with::<N, TripleId>(hdts, keep)
+    }
+}
+
+fn build_with<const N: usize, T: Buffered>(hdts: [&Hdt; N], keep: [&dyn Fn(&TripleId) -> bool; N]) -> Hdt {
+    let mut triples: Vec<T> = Vec::new();
+    let mut ends = [0; N];
+    let used: [Used; N] = std::array::from_fn(|i| {
+        let dict = &hdts[i].dict;
+        let mut used = Used {
+            subjects: vec![false; subject_len(dict)],
+            predicates: vec![false; predicate_len(dict)],
+            objects: vec![false; object_len(dict)],
+        };
+        for t in SubjectIter::new(&hdts[i].triples).filter(keep[i]) {
+            used.subjects[t.subject_id] = true;
+            used.predicates[t.predicate_id] = true;
+            used.objects[t.object_id] = true;
+            triples.push(T::pack(t));
+        }
+        ends[i] = triples.len();
+        used
+    });
+    let (dict, maps) = build_dict(hdts.map(|h| &h.dict), &used);
+    drop(used);
+    debug!("built dictionary {dict:#?}");
+
+    // the order of the IDs changes when terms are added or move between sections
+    let mut start = 0;
+    for (i, end) in ends.into_iter().enumerate() {
+        let [s, p, o] = &maps[i];
+        for t in &mut triples[start..end] {
+            let old = t.unpack();
+            *t = T::pack(TripleId::new(s[old.subject_id], p[old.predicate_id], o[old.object_id]));
+        }
+        start = end;
+    }
+    drop(maps);
+    triples.sort_unstable();
+    triples.dedup();
+    let triples = TriplesBitmap::from_triples(triples.into_iter().map(T::unpack));
+    Hdt { header: Header::default(), dict, triples }
+}
+
+/// HDT with the given triples in the string format of the dictionary, for example to merge a few triples into a large HDT.
+/// All terms are kept in memory, so this is only suitable for small graphs.
+pub(crate) fn hdt_from_triples(triples: &[[&str; 3]]) -> Hdt {
+    let set = |i: usize| triples.iter().map(|t| t[i]).collect::<BTreeSet<_>>();
+    let (s, p, o) = (set(0), set(1), set(2));
+    let build = |terms: &mut dyn Iterator<Item = &&str>| {
+        let mut builder = DictSectPFCBuilder::new(DictSectPFCBuilder::DEFAULT_BLOCK_SIZE);
+        terms.for_each(|t| builder.push(t.as_bytes()));
+        builder.build()
+    };
+    let dict = FourSectDict::new(
+        build(&mut s.intersection(&o)),
+        build(&mut s.difference(&o)),
+        build(&mut p.iter()),
+        build(&mut o.difference(&s)),
+    );
+    let mut ids: Vec<TripleId> = triples
+        .iter()
+        .map(|t| {
+            TripleId::new(
+                dict.string_to_id(t[0], &IdKind::Subject),
+                dict.string_to_id(t[1], &IdKind::Predicate),
+                dict.string_to_id(t[2], &IdKind::Object),
+            )
+        })
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    Hdt { header: Header::default(), dict, triples: TriplesBitmap::from_triples(ids) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{hdt, sorted};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn walk_terms() {
+        let (x, y, z) = ("http://example.org/x", "http://example.org/y", "http://example.org/z");
+        let p = "http://example.org/p";
+        let left = hdt(&[[x, p, y], [y, p, z]]);
+        let right = hdt(&[[z, p, x]]);
+        let mut terms = Vec::new();
+        position_terms([&left.dict, &right.dict], |term, ids| {
+            terms.push((String::from_utf8(term.to_vec()).unwrap(), ids));
+        });
+        // y is shared in left, x and z are subject-only and object-only in left and the other way around in right
+        assert_eq!(
+            vec![
+                (x.to_owned(), [[2, 0], [0, 1]]),
+                (y.to_owned(), [[1, 0], [1, 0]]),
+                (z.to_owned(), [[0, 1], [2, 0]]),
+            ],
+            terms
+        );
+    }
+
+    #[test]
+    fn build_filtered() {
+        let (x, y, z) = ("http://example.org/x", "http://example.org/y", "http://example.org/z");
+        let (p, p2) = ("http://example.org/p", "http://example.org/p2");
+        let left = hdt(&[[x, p, y], [y, p2, z]]);
+        let right = hdt(&[[x, p, y], [z, p, "\"literal\""]]);
+        // y is only used as an object and p2 is unused after dropping the second triple of left
+        let p2_id = left.dict.string_to_id(p2, &IdKind::Predicate);
+        let merged = build([&left, &right], [&|t: &TripleId| t.predicate_id != p2_id, &|_: &TripleId| true]);
+        let expected: Vec<[String; 3]> =
+            vec![[x, p, y].map(str::to_owned), [z, p, "\"literal\""].map(str::to_owned)];
+        assert_eq!(expected, sorted(&merged));
+        assert_eq!(0, merged.dict.shared.num_strings());
+        assert_eq!(1, merged.dict.predicates.num_strings());
+        assert_eq!(0, merged.dict.string_to_id(p2, &IdKind::Predicate));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::hdt::OwnedTriples;
+use crate::Hdt;
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::str::Utf8Error;
+use std::sync::Arc;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: impl Display) {
+    let message = CString::new(e.to_string().replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f` and return `on_panic` if it panics, with the panic message as the last error,
+/// because unwinding into C is undefined behavior.
+fn catch<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(s), _) => s,
+            (_, Some(s)) => s.as_str(),
+            _ => "unknown cause",
+        };
+        set_error(format!("panic: {message}"));
+        on_panic
+    })
+}
+
+/// Loaded HDT file, opaque to C.
+pub struct HdtHandle {
+    hdt: Arc<Hdt>,
+}
+
+/// Iterator over the triples of a search, opaque to C.
+/// Keeps the HDT alive, so it stays valid after [`hdt_free`].
+pub struct HdtIter {
+    triples: OwnedTriples,
+    // C strings of the current triple
+    terms: [CString; 3],
+}
+
+/// Optional term from a C string, where NULL stands for a variable.
+unsafe fn term<'a>(s: *const c_char) -> Result<Option<&'a str>, Utf8Error> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s).to_str().map(Some)
+}
+
+/// Triple pattern from C strings, sets the last error and returns `None` if a term is not valid UTF-8.
+unsafe fn pattern<'a>(
+    s: *const c_char, p: *const c_char, o: *const c_char,
+) -> Option<(Option<&'a str>, Option<&'a str>, Option<&'a str>)> {
+    let pattern = || Ok::<_, Utf8Error>((term(s)?, term(p)?, term(o)?));
+    pattern().map_err(|e| set_error(format!("term is not valid UTF-8: {e}"))).ok()
+}
+
+/// Message of the last error on the current thread or NULL if there was none.
+/// Panics in the library, such as failed checksums while loading, are reported as errors as well.
+/// Valid until the next error on the same thread.
+#[no_mangle]
+pub extern "C" fn hdt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Load the HDT file at the given path.
+/// Returns NULL on error, see [`hdt_last_error`].
+/// # Safety
+/// `path` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_open(path: *const c_char) -> *mut HdtHandle {
+    catch(ptr::null_mut(), || {
+        let open = || -> Result<Hdt, Box<dyn Error>> {
+            let path = term(path)?.ok_or("path is NULL")?;
+            Ok(Hdt::new(BufReader::new(File::open(path)?))?)
+        };
+        match open() {
+            Ok(hdt) => Box::into_raw(Box::new(HdtHandle { hdt: Arc::new(hdt) })),
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Free an HDT returned by [`hdt_open`]. Iterators of the HDT stay valid until they are freed themselves.
+/// # Safety
+/// `hdt` must be NULL or returned by [`hdt_open`] and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_free(hdt: *mut HdtHandle) {
+    catch((), || {
+        if !hdt.is_null() {
+            drop(Box::from_raw(hdt));
+        }
+    })
+}
+
+/// Search for all triples that fit the given triple pattern, where NULL stands for a variable.
+/// Terms are in the same format as in [`Hdt::triples_with_pattern`].
+/// Returns NULL on error, see [`hdt_last_error`].
+/// # Safety
+/// `hdt` must be returned by [`hdt_open`] and the terms must be NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_search(
+    hdt: *const HdtHandle, s: *const c_char, p: *const c_char, o: *const c_char,
+) -> *mut HdtIter {
+    catch(ptr::null_mut(), || {
+        let Some(handle) = hdt.as_ref() else {
+            set_error("hdt is NULL");
+            return ptr::null_mut();
+        };
+        let Some((s, p, o)) = pattern(s, p, o) else {
+            return ptr::null_mut();
+        };
+        let triples = handle.hdt.clone().triples_with_pattern_owned(s, p, o);
+        Box::into_raw(Box::new(HdtIter { triples, terms: Default::default() }))
+    })
+}
+
+/// Advance the iterator and point `s`, `p` and `o` to the terms of the next triple.
+/// The terms stay valid until the next call on the same iterator or [`hdt_iter_free`].
+/// Returns 1 for a triple, 0 at the end and -1 on error, see [`hdt_last_error`].
+/// # Safety
+/// `iter` must be returned by [`hdt_search`] and the term pointers must be NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_iter_next(
+    iter: *mut HdtIter, s: *mut *const c_char, p: *mut *const c_char, o: *mut *const c_char,
+) -> c_int {
+    catch(-1, || {
+        let Some(iter) = iter.as_mut() else {
+            set_error("iter is NULL");
+            return -1;
+        };
+        let Some((ts, tp, to)) = iter.triples.next() else {
+            return 0;
+        };
+        for (i, t) in [ts, tp, to].iter().enumerate() {
+            match CString::new(t.as_bytes()) {
+                Ok(t) => iter.terms[i] = t,
+                Err(e) => {
+                    set_error(e);
+                    return -1;
+                }
+            }
+        }
+        for (out, t) in [s, p, o].into_iter().zip(&iter.terms) {
+            if !out.is_null() {
+                *out = t.as_ptr();
+            }
+        }
+        1
+    })
+}
+
+/// Advance the iterator and write the IDs of the next triple to `s`, `p` and `o` without decoding the terms.
+/// Returns 1 for a triple, 0 at the end and -1 on error.
+/// # Safety
+/// `iter` must be returned by [`hdt_search`] and the ID pointers must be NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_iter_next_ids(iter: *mut HdtIter, s: *mut u64, p: *mut u64, o: *mut u64) -> c_int {
+    catch(-1, || {
+        let Some(iter) = iter.as_mut() else {
+            set_error("iter is NULL");
+            return -1;
+        };
+        let Some(t) = iter.triples.next_id() else {
+            return 0;
+        };
+        for (out, id) in [(s, t.subject_id), (p, t.predicate_id), (o, t.object_id)] {
+            if !out.is_null() {
+                *out = id as u64;
+            }
+        }
+        1
+    })
+}
+
+/// Free an iterator returned by [`hdt_search`].
+/// # Safety
+/// `iter` must be NULL or returned by [`hdt_search`] and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_iter_free(iter: *mut HdtIter) {
+    catch((), || {
+        if !iter.is_null() {
+            drop(Box::from_raw(iter));
+        }
+    })
+}
+
+/// Write the number of triples that fit the given triple pattern, where NULL stands for a variable, to `count`,
+/// see [`Hdt::pattern_count`].
+/// Returns 0 on success and -1 on error, see [`hdt_last_error`], so that an error can't be mistaken for an empty result.
+/// # Safety
+/// `hdt` must be returned by [`hdt_open`], the terms must be NULL or valid NUL-terminated strings
+/// and `count` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hdt_count(
+    hdt: *const HdtHandle, s: *const c_char, p: *const c_char, o: *const c_char, count: *mut usize,
+) -> c_int {
+    catch(-1, || {
+        let Some(handle) = hdt.as_ref() else {
+            set_error("hdt is NULL");
+            return -1;
+        };
+        if count.is_null() {
+            set_error("count is NULL");
+            return -1;
+        }
+        let Some((s, p, o)) = pattern(s, p, o) else {
+            return -1;
+        };
+        *count = handle.hdt.pattern_count(s, p, o);
+        0
+    })
+}
@@ -0,0 +1,70 @@
+//! Merge two HDT files into one without converting them to RDF text and back, similar to hdtCat in hdt-java.
+use crate::builder::build;
+use crate::Hdt;
+
+/// Merge two HDT files into a new one that contains the triples of both without duplicates.
+/// The dictionary sections are merged in a single pass over the sorted terms and the IDs of the inputs are remapped.
+/// A term that only occurs as a subject in one file and only as an object in the other moves to the shared section.
+/// The merged HDT has an empty header because the metadata of the inputs doesn't apply to it.
+pub fn cat(a: &Hdt, b: &Hdt) -> Hdt {
+    build([a, b], [&|_| true, &|_| true])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{hdt, init, snikmeta, sorted};
+    use crate::IdKind;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cat_small() {
+        let (x, y, z) = ("http://example.org/x", "http://example.org/y", "http://example.org/z");
+        let (p, p2) = ("http://example.org/p", "http://example.org/p2");
+        // x is subject-only in left and object-only in right, y is object-only in both
+        let left = hdt(&[[x, p, y], [x, p2, "\"literal\"@en"]]);
+        let right = hdt(&[[z, p2, x], [x, p, y]]);
+        assert_eq!(0, left.dict.shared.num_strings());
+        let merged = cat(&left, &right);
+        assert_eq!(1, merged.dict.shared.num_strings());
+        assert_eq!(1, merged.dict.string_to_id(x, &IdKind::Subject));
+        assert_eq!(1, merged.dict.string_to_id(x, &IdKind::Object));
+        assert_eq!(1, merged.dict.subjects.num_strings());
+        assert_eq!(2, merged.dict.objects.num_strings());
+        assert_eq!(2, merged.dict.predicates.num_strings());
+        let mut expected: Vec<[String; 3]> =
+            [[x, p, y], [x, p2, "\"literal\"@en"], [z, p2, x]].iter().map(|t| t.map(str::to_owned)).collect();
+        expected.sort();
+        assert_eq!(expected, sorted(&merged));
+        let with_x: Vec<_> = merged
+            .triples_with_pattern(None, None, Some(x))
+            .map(|(s, p, o)| [s.to_string(), p.to_string(), o.to_string()])
+            .collect();
+        assert_eq!(vec![[z, p2, x].map(str::to_owned)], with_x);
+        assert_eq!(2, merged.triples_with_pattern(None, Some(p2), None).count());
+    }
+
+    #[test]
+    fn cat_snikmeta() {
+        init();
+        let snik = snikmeta();
+        let extra =
+            hdt(&[["http://www.snik.eu/ontology/meta/Top", "http://example.org/p", "http://example.org/o"]]);
+        // duplicates are removed
+        let same = cat(snik, snik);
+        assert_eq!(sorted(snik), sorted(&same));
+        assert_eq!(snik.dict.shared.num_strings(), same.dict.shared.num_strings());
+        let merged = cat(snik, &extra);
+        let mut expected = sorted(snik);
+        expected.extend(sorted(&extra));
+        expected.sort();
+        assert_eq!(expected, sorted(&merged));
+        for [s, p, o] in &expected {
+            assert_eq!(
+                1,
+                merged.pattern_count(Some(s.as_str()), Some(p.as_str()), Some(o.as_str())),
+                "{s} {p} {o}"
+            );
+        }
+    }
+}
@@ -1,12 +1,12 @@
 //! Bitmap with rank and select support read from an HDT file.
-use crate::containers::vbyte::read_vbyte;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
 use bytesize::ByteSize;
 use crc_any::{CRCu32, CRCu8};
 use rsdict::RsDict;
 use std::convert::TryFrom;
 use std::fmt;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::mem::size_of;
 
 //const USIZE_BITS: usize = usize::BITS as usize;
@@ -91,7 +91,7 @@ impl Bitmap {
         }
 
         // read all but the last word, last word is byte aligned
-        let full_byte_amount = ((num_bits - 1) >> 6) * 8;
+        let full_byte_amount = (num_bits.saturating_sub(1) >> 6) * 8;
         let mut full_words = vec![0_u8; full_byte_amount];
         // div_ceil is unstable
         let mut data: Vec<u64> = Vec::with_capacity(full_byte_amount / 8 + usize::from(full_byte_amount % 8 != 0));
@@ -136,4 +136,50 @@ impl Bitmap {
 
         Ok(Self::new(data))
     }
+
+    /// Write the first `num_bits` bits including metadata and checksums in the format expected by [`Self::read`].
+    /// The number of bits is needed because a bitmap that was read is padded to whole 64 bit blocks.
+    pub fn write<W: Write>(&self, w: &mut W, num_bits: usize) -> io::Result<()> {
+        let mut meta = vec![1];
+        meta.extend_from_slice(&encode_vbyte(num_bits));
+        let mut crc = CRCu8::crc8();
+        crc.digest(&meta[..]);
+        meta.push(crc.get_crc());
+        w.write_all(&meta)?;
+        // little endian 64 bit words with the last one truncated to full bytes
+        let mut body = vec![0_u8; num_bits.div_ceil(8)];
+        for i in 0..num_bits {
+            if self.dict.get_bit(i as u64) {
+                body[i / 8] |= 1 << (i % 8);
+            }
+        }
+        let mut crc = CRCu32::crc32c();
+        crc.digest(&body[..]);
+        w.write_all(&body)?;
+        w.write_all(&crc.get_crc().to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn write() {
+        init();
+        for num_bits in [0, 1, 63, 64, 65, 200] {
+            let mut dict = RsDict::new();
+            for i in 0..num_bits {
+                dict.push(i % 3 == 0 || i % 7 == 0);
+            }
+            let bitmap = Bitmap { dict };
+            let mut written = Vec::new();
+            bitmap.write(&mut written, num_bits).unwrap();
+            let read = Bitmap::read(&mut &written[..]).unwrap();
+            let bits = |b: &Bitmap| (0..num_bits as u64).map(|i| b.dict.get_bit(i)).collect::<Vec<_>>();
+            assert_eq!(bits(&bitmap), bits(&read), "{num_bits} bits");
+        }
+    }
 }
@@ -1,8 +1,8 @@
 use crc_any::CRCu16;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::str;
 
 /// Type of Control Information.
@@ -41,8 +41,8 @@ pub struct ControlInfo {
     pub control_type: ControlType,
     /// "URI identifier of the implementation of the following section."
     pub format: String,
-    /// Key-value entries, ASCII only.
-    properties: HashMap<String, String>,
+    /// Key-value entries, ASCII only, sorted so that they are written in a deterministic order.
+    properties: BTreeMap<String, String>,
 }
 
 impl ControlInfo {
@@ -91,7 +91,7 @@ impl ControlInfo {
             unreachable!();
         }
         let prop_str = String::from_utf8(prop_str).map_err(|e| Error::new(InvalidData, e))?;
-        let mut properties = HashMap::new();
+        let mut properties = BTreeMap::new();
         for item in prop_str.split(';') {
             if let Some(index) = item.find('=') {
                 let (key, val) = item.split_at(index);
@@ -114,10 +114,36 @@ impl ControlInfo {
         Ok(ControlInfo { control_type, format, properties })
     }
 
+    /// Control information of the given type and format without properties.
+    pub fn new(control_type: ControlType, format: &str) -> Self {
+        ControlInfo { control_type, format: format.to_owned(), properties: BTreeMap::new() }
+    }
+
     /// Get property value for the given key, if available.
     pub fn get(&self, key: &str) -> Option<String> {
         self.properties.get(key).cloned()
     }
+
+    /// Set the property with the given key, which must not contain `=` or `;`.
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.properties.insert(key.to_owned(), value.to_string());
+    }
+
+    /// Write the control information including the CRC in the format expected by [`Self::read`].
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut data = b"$HDT".to_vec();
+        data.push(self.control_type as u8);
+        data.extend_from_slice(self.format.as_bytes());
+        data.push(0);
+        for (key, value) in &self.properties {
+            data.extend_from_slice(format!("{key}={value};").as_bytes());
+        }
+        data.push(0);
+        let mut crc = CRCu16::crc16();
+        crc.digest(&data[..]);
+        w.write_all(&data)?;
+        w.write_all(&crc.get_crc().to_le_bytes())
+    }
 }
 
 #[cfg(test)]
@@ -140,4 +166,23 @@ mod tests {
             panic!("Failed to read control info");
         }
     }
+
+    #[test]
+    fn write_info() {
+        init();
+        let info = b"$HDT\x01<http://purl.org/HDT/hdt#HDTv1>\x00\x00\x76\x35";
+        let mut written = Vec::new();
+        ControlInfo::new(ControlType::Global, "<http://purl.org/HDT/hdt#HDTv1>").write(&mut written).unwrap();
+        assert_eq!(&info[..], &written[..]);
+
+        let mut ci = ControlInfo::new(ControlType::Triples, "<http://purl.org/HDT/hdt#triplesBitmap>");
+        ci.set("order", 1);
+        ci.set("numTriples", 327);
+        let mut written = Vec::new();
+        ci.write(&mut written).unwrap();
+        let read = ControlInfo::read(&mut BufReader::new(&written[..])).unwrap();
+        assert_eq!(ControlType::Triples, read.control_type);
+        assert_eq!(ci.format, read.format);
+        assert_eq!(ci.properties, read.properties);
+    }
 }
@@ -0,0 +1,40 @@
+//! Work such as CRC validation that runs in the background where threads are available.
+use std::fmt;
+use std::thread;
+
+/// Result of a computation that runs on a separate thread, or that was already computed on the current thread
+/// on targets without thread support such as `wasm32-unknown-unknown`.
+pub enum Deferred<T> {
+    /// Computation running on a separate thread.
+    Thread(thread::JoinHandle<T>),
+    /// Finished computation.
+    Done(T),
+}
+
+impl<T> fmt::Debug for Deferred<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deferred::Thread(_) => write!(f, "Deferred::Thread"),
+            Deferred::Done(_) => write!(f, "Deferred::Done"),
+        }
+    }
+}
+
+impl<T: Send + 'static> Deferred<T> {
+    /// Run the given function on a new thread or, on wasm32, immediately on the current thread.
+    pub fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Deferred::Done(f())
+        } else {
+            Deferred::Thread(thread::spawn(f))
+        }
+    }
+
+    /// Wait for the result, which is an error if the thread panicked, like [`thread::JoinHandle::join`].
+    pub fn join(self) -> thread::Result<T> {
+        match self {
+            Deferred::Thread(handle) => handle.join(),
+            Deferred::Done(t) => Ok(t),
+        }
+    }
+}
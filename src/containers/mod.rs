@@ -9,10 +9,14 @@ mod adj_list;
 mod bitmap;
 mod sequence;
 
+// background work
+mod deferred;
+
 // control info section reader
 mod control_info;
 
 pub use adj_list::AdjList;
 pub use bitmap::Bitmap;
 pub use control_info::{ControlInfo, ControlType};
+pub use deferred::Deferred;
 pub use sequence::Sequence;
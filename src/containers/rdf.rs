@@ -35,6 +35,18 @@ pub enum Id {
     Blank(String),
 }
 
+impl Id {
+    /// IRI or blank node in the string format of the HDT dictionary,
+    /// where IRIs have no angle brackets and blank nodes start with `_:`.
+    pub fn to_hdt_string(&self) -> String {
+        match self {
+            Id::Named(iri) => iri.clone(),
+            Id::Blank(id) if id.starts_with("_:") => id.clone(),
+            Id::Blank(id) => format!("_:{id}"),
+        }
+    }
+}
+
 // There's a custom debug implementation to hide the enum variant tag when printing,
 // it saves some screen space that's not needed.
 impl fmt::Debug for Id {
@@ -55,6 +67,21 @@ pub enum Term {
     Literal(Literal),
 }
 
+impl Term {
+    /// Term in the string format of the HDT dictionary, see [`Id::to_hdt_string`].
+    /// The lexical form of literals is not escaped.
+    pub fn to_hdt_string(&self) -> String {
+        match self {
+            Term::Id(id) => id.to_hdt_string(),
+            Term::Literal(lit) => match (lit.lang(), lit.datatype()) {
+                (Some(lang), _) => format!("\"{}\"@{lang}", lit.form()),
+                (None, Some(datatype)) => format!("\"{}\"^^<{datatype}>", lit.form()),
+                (None, None) => format!("\"{}\"", lit.form()),
+            },
+        }
+    }
+}
+
 // There's a custom debug implementation to hide the enum variant tag when printing,
 // it saves some screen space that's not needed.
 impl fmt::Debug for Term {
@@ -137,4 +164,19 @@ impl Literal {
 
         Literal { form, datatype: Some(datatype), lang: Some(lang) }
     }
+
+    /// Lexical form.
+    pub fn form(&self) -> &str {
+        &self.form
+    }
+
+    /// Datatype IRI, `None` for the default type xs:string.
+    pub fn datatype(&self) -> Option<&str> {
+        self.datatype.as_deref()
+    }
+
+    /// Language tag, only for language tagged strings.
+    pub fn lang(&self) -> Option<&str> {
+        self.lang.as_deref()
+    }
 }
@@ -1,13 +1,13 @@
-use crate::containers::vbyte::read_vbyte;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
+use crate::containers::Deferred;
 use bytesize::ByteSize;
 use crc_any::{CRCu32, CRCu8};
 use std::fmt;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::mem::size_of;
-use std::thread;
 
-const USIZE_BITS: usize = usize::BITS as usize;
+const WORD_BITS: usize = u64::BITS as usize;
 
 /// Integer sequence with a given number of bits, which means numbers may be represented along byte boundaries.
 //#[derive(Clone)]
@@ -16,10 +16,10 @@ pub struct Sequence {
     pub entries: usize,
     /// Number of bits that each integer uses.
     pub bits_per_entry: usize,
-    /// Data in blocks.
-    pub data: Vec<usize>,
+    /// Data in little endian 64 bit blocks, independent of the platform word size.
+    pub data: Vec<u64>,
     /// whether CRC check was successful
-    pub crc_handle: Option<thread::JoinHandle<bool>>,
+    pub crc_handle: Option<Deferred<bool>>,
 }
 
 impl fmt::Debug for Sequence {
@@ -61,29 +61,59 @@ impl<'a> IntoIterator for &'a Sequence {
 }
 
 impl Sequence {
+    /// Pack the given integers into a sequence using the given number of bits for each of them.
+    /// Panics if a value doesn't fit into the given number of bits.
+    pub fn new(values: &[usize], bits_per_entry: usize) -> Self {
+        assert!(bits_per_entry <= WORD_BITS, "entry size too large (>{WORD_BITS} bit)");
+        // as in read, there is always at least one block
+        let mut data = vec![0_u64; (values.len() * bits_per_entry).div_ceil(WORD_BITS).max(1)];
+        for (index, &value) in values.iter().enumerate() {
+            let value = value as u64;
+            assert!(
+                bits_per_entry == WORD_BITS || value >> bits_per_entry == 0,
+                "{value} does not fit into {bits_per_entry} bits"
+            );
+            let (block_index, bit_index) = Self::locate(index, bits_per_entry);
+            data[block_index] |= value << bit_index;
+            if bit_index + bits_per_entry > WORD_BITS {
+                data[block_index + 1] |= value >> (WORD_BITS - bit_index);
+            }
+        }
+        Sequence { entries: values.len(), bits_per_entry, data, crc_handle: None }
+    }
+
+    /// Block index and bit index within that block of the entry with the given index.
+    /// Calculated with 64 bits so that the bit position doesn't overflow on 32 bit platforms.
+    const fn locate(index: usize, bits_per_entry: usize) -> (usize, usize) {
+        let scaled_index = index as u64 * bits_per_entry as u64;
+        ((scaled_index / WORD_BITS as u64) as usize, (scaled_index % WORD_BITS as u64) as usize)
+    }
+
     /// Get the integer at the given index, counting from 0.
+    /// On 32 bit platforms, entries with more than 32 bits are truncated.
     pub fn get(&self, index: usize) -> usize {
-        let scaled_index = index * self.bits_per_entry;
-        let block_index = scaled_index / USIZE_BITS;
-        let bit_index = scaled_index % USIZE_BITS;
+        if self.bits_per_entry == 0 {
+            return 0;
+        }
+        let (block_index, bit_index) = Self::locate(index, self.bits_per_entry);
 
         let mut result;
 
-        let result_shift = USIZE_BITS - self.bits_per_entry;
-        if bit_index + self.bits_per_entry <= USIZE_BITS {
-            let block_shift = USIZE_BITS - bit_index - self.bits_per_entry;
+        let result_shift = WORD_BITS - self.bits_per_entry;
+        if bit_index + self.bits_per_entry <= WORD_BITS {
+            let block_shift = WORD_BITS - bit_index - self.bits_per_entry;
             result = (self.data[block_index] << block_shift) >> result_shift;
         } else {
-            let block_shift = (USIZE_BITS << 1) - bit_index - self.bits_per_entry;
+            let block_shift = (WORD_BITS << 1) - bit_index - self.bits_per_entry;
             result = self.data[block_index] >> bit_index;
-            result |= (self.data[(block_index + 1)] << block_shift) >> result_shift;
+            result |= (self.data[block_index + 1] << block_shift) >> result_shift;
         }
-        result
+        result as usize
     }
 
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
-        (self.data.len() * USIZE_BITS) >> 3
+        self.data.len() * size_of::<u64>()
     }
 
     /// Read sequence including metadata from HDT data.
@@ -109,9 +139,13 @@ impl Sequence {
         reader.read_exact(&mut buffer)?;
         history.extend_from_slice(&buffer);
         let bits_per_entry = buffer[0] as usize;
-        if bits_per_entry > USIZE_BITS {
+        if bits_per_entry > WORD_BITS {
             return Err(Error::new(InvalidData, "entry size too large (>64 bit)"));
         }
+        // entries are returned as usize, so on 32 bit platforms they must not exceed 32 bit
+        if bits_per_entry > usize::BITS as usize {
+            return Err(Error::new(InvalidData, "entry size too large for usize on this platform"));
+        }
 
         // read number of entries
         let (entries, bytes_read) = read_vbyte(reader)?;
@@ -131,43 +165,45 @@ impl Sequence {
 
         // read body data
         // read all but the last entry, since the last one is byte aligned
-        let total_bits = bits_per_entry * entries;
+        // calculated with 64 bits so that it doesn't overflow on 32 bit platforms
+        let total_bits = bits_per_entry as u64 * entries as u64;
         let full_byte_amount =
-            (((total_bits + USIZE_BITS - 1) / USIZE_BITS).saturating_sub(1)) * size_of::<usize>();
+            usize::try_from(total_bits.div_ceil(WORD_BITS as u64).saturating_sub(1) * size_of::<u64>() as u64)
+                .map_err(|_| Error::new(InvalidData, "sequence too large for this platform"))?;
         let mut full_words = vec![0_u8; full_byte_amount];
         reader.read_exact(&mut full_words)?;
-        let mut data: Vec<usize> = Vec::with_capacity(full_byte_amount / 8 + 2);
+        let mut data: Vec<u64> = Vec::with_capacity(full_byte_amount / 8 + 2);
         // read entry body
         // keep track of history for CRC32
         let mut history: Vec<u8> = Vec::with_capacity(full_byte_amount + 8);
         history.extend_from_slice(&full_words);
 
-        // turn the raw bytes into usize values
-        for word in full_words.chunks_exact(size_of::<usize>()) {
+        // turn the raw bytes into u64 values
+        for word in full_words.chunks_exact(size_of::<u64>()) {
             if let Ok(word_data) = <[u8; 8]>::try_from(word) {
-                data.push(usize::from_le_bytes(word_data));
+                data.push(u64::from_le_bytes(word_data));
             } else {
-                return Err(Error::new(Other, "failed to read usize"));
+                return Err(Error::new(Other, "failed to read u64"));
             }
         }
 
         // read the last few bits, byte aligned
         let mut bits_read = 0;
-        let mut last_value: usize = 0;
-        let last_entry_bits = if total_bits == 0 { 0 } else { ((total_bits - 1) % USIZE_BITS) + 1 };
+        let mut last_value: u64 = 0;
+        let last_entry_bits = if total_bits == 0 { 0 } else { ((total_bits - 1) % WORD_BITS as u64) + 1 };
 
         while bits_read < last_entry_bits {
             let mut buffer = [0u8];
             reader.read_exact(&mut buffer)?;
             history.extend_from_slice(&buffer);
-            last_value |= (buffer[0] as usize) << bits_read;
-            bits_read += size_of::<usize>();
+            last_value |= (buffer[0] as u64) << bits_read;
+            bits_read += 8;
         }
         data.push(last_value);
         // read entry body CRC32
         let mut crc_code = [0_u8; 4];
         reader.read_exact(&mut crc_code)?;
-        let crc_handle = Some(thread::spawn(move || {
+        let crc_handle = Some(Deferred::spawn(move || {
             let crc_code = u32::from_le_bytes(crc_code);
 
             // validate entry body CRC32
@@ -178,4 +214,119 @@ impl Sequence {
 
         Ok(Sequence { entries, bits_per_entry, data, crc_handle })
     }
+
+    /// Write the sequence including metadata and checksums in the format expected by [`Self::read`].
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut meta = vec![1, self.bits_per_entry as u8];
+        meta.extend_from_slice(&encode_vbyte(self.entries));
+        let mut crc = CRCu8::crc8();
+        crc.digest(&meta[..]);
+        meta.push(crc.get_crc());
+        w.write_all(&meta)?;
+        // little endian 64 bit words with the last one truncated to full bytes
+        let total_bytes = (self.bits_per_entry as u64 * self.entries as u64).div_ceil(8) as usize;
+        let body: Vec<u8> = self.data.iter().flat_map(|word| word.to_le_bytes()).take(total_bytes).collect();
+        let mut crc = CRCu32::crc32c();
+        crc.digest(&body[..]);
+        w.write_all(&body)?;
+        w.write_all(&crc.get_crc().to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn new() {
+        init();
+        let values = [0, 1, 5, 1000, 0, 1023];
+        let sequence = Sequence::new(&values, 10);
+        assert_eq!(values.to_vec(), sequence.into_iter().collect::<Vec<_>>());
+        let empty = Sequence::new(&[], 0);
+        assert_eq!(0, empty.into_iter().count());
+        let zeros = Sequence::new(&[0, 0], 0);
+        assert_eq!(vec![0, 0], zeros.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn read() {
+        init();
+        // 20 bit entries straddle both 32 and 64 bit word boundaries
+        let values: Vec<usize> = (0..50).map(|i| i * 40_503 % (1 << 20)).collect();
+        let sequence = Sequence::new(&values, 20);
+        let mut bytes = vec![1, 20];
+        bytes.extend_from_slice(&encode_vbyte(values.len()));
+        let mut crc = CRCu8::crc8();
+        crc.digest(&bytes[..]);
+        bytes.push(crc.get_crc());
+        // the format stores little endian 64 bit words with the last one truncated to full bytes
+        let body: Vec<u8> = sequence
+            .data
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take((values.len() * 20).div_ceil(8))
+            .collect();
+        let mut crc = CRCu32::crc32c();
+        crc.digest(&body[..]);
+        bytes.extend_from_slice(&body);
+        bytes.extend_from_slice(&crc.get_crc().to_le_bytes());
+
+        let mut written = Vec::new();
+        sequence.write(&mut written).unwrap();
+        assert_eq!(bytes, written);
+
+        let mut read = Sequence::read(&mut &bytes[..]).unwrap();
+        assert!(read.crc_handle.take().unwrap().join().unwrap());
+        assert_eq!(sequence.data, read.data);
+        assert_eq!(values, read.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn over_u32() {
+        init();
+        let boundary = 1 << 32;
+        let values = [0, 1, u32::MAX as usize, boundary, boundary + 1, 7, (1 << 33) - 1, boundary - 1];
+        // entries that straddle blocks on both sides of the boundary
+        for bits_per_entry in [33, 40, 41, 63, 64] {
+            let sequence = Sequence::new(&values, bits_per_entry);
+            assert_eq!(values.len(), sequence.entries);
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(value, sequence.get(i), "index {i} with {bits_per_entry} bits per entry");
+            }
+        }
+        let sequence = Sequence::new(&[usize::MAX, 0, usize::MAX], 64);
+        assert_eq!(vec![usize::MAX, 0, usize::MAX], sequence.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn too_wide() {
+        init();
+        for bits_per_entry in [33_u8, 64, 65] {
+            let mut bytes = vec![1, bits_per_entry];
+            bytes.extend_from_slice(&encode_vbyte(1));
+            let mut crc = CRCu8::crc8();
+            crc.digest(&bytes[..]);
+            bytes.push(crc.get_crc());
+            let body = vec![0xFF; usize::from(bits_per_entry).div_ceil(8)];
+            let mut crc = CRCu32::crc32c();
+            crc.digest(&body[..]);
+            bytes.extend_from_slice(&body);
+            bytes.extend_from_slice(&crc.get_crc().to_le_bytes());
+            // 33 to 64 bits only fit into usize on 64 bit platforms
+            match Sequence::read(&mut &bytes[..]) {
+                Ok(sequence) => {
+                    assert!(u32::from(bits_per_entry) <= usize::BITS, "{bits_per_entry} bits per entry accepted");
+                    assert_eq!(u64::MAX >> (64 - bits_per_entry), sequence.get(0) as u64);
+                }
+                Err(e) => {
+                    assert!(u32::from(bits_per_entry) > usize::BITS, "{bits_per_entry} bits per entry rejected");
+                    assert_eq!(io::ErrorKind::InvalidData, e.kind());
+                }
+            }
+        }
+    }
 }
@@ -1,7 +1,7 @@
 /// Dictionary section with plain front coding.
 /// See <https://www.rdfhdt.org/hdt-binary-format/#DictionarySectionPlainFrontCoding>.
-use crate::containers::vbyte::{decode_vbyte_delta, read_vbyte};
-use crate::containers::Sequence;
+use crate::containers::vbyte::{decode_vbyte_delta, encode_vbyte, read_vbyte};
+use crate::containers::{Deferred, Sequence};
 use crate::triples::Id;
 use bytesize::ByteSize;
 use crc_any::{CRCu32, CRCu8};
@@ -9,9 +9,8 @@ use log::error;
 use std::cmp::{min, Ordering};
 use std::fmt;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::str;
-use std::thread;
 use thiserror::Error;
 
 /// Dictionary section with plain front coding.
@@ -22,7 +21,7 @@ pub struct DictSectPFC {
     sequence: Sequence,
     packed_data: Vec<u8>,
     /// whether CRC check was successful
-    pub crc_handle: Option<thread::JoinHandle<bool>>,
+    pub crc_handle: Option<Deferred<bool>>,
 }
 
 impl fmt::Debug for DictSectPFC {
@@ -201,6 +200,19 @@ impl DictSectPFC {
 
     /// extract the string with the given ID from the dictionary
     pub fn extract(&self, id: Id) -> Result<String, ExtractError> {
+        let mut string = Vec::new();
+        self.extract_into(id, &mut string)?;
+        // tried simdutf8::basic::from_utf8 but that didn't speed up extract that much
+        String::from_utf8(string).map_err(|e| {
+            let source = e.utf8_error();
+            let data = e.into_bytes();
+            ExtractError::InvalidUtf8 { source, recovered: String::from_utf8_lossy(&data).into_owned(), data }
+        })
+    }
+
+    /// Extract the string with the given ID into a caller-provided buffer, which is cleared first.
+    /// Reusing the buffer avoids an allocation per term. The bytes are not validated as UTF-8.
+    pub fn extract_into(&self, id: Id, buffer: &mut Vec<u8>) -> Result<(), ExtractError> {
         if id as usize > self.num_strings {
             return Err(ExtractError::IdOutOfBounds { id, len: self.num_strings });
         }
@@ -208,7 +220,8 @@ impl DictSectPFC {
         let string_index = id.saturating_sub(1) as usize % self.block_size;
         let mut position = self.sequence.get(block_index);
         let mut slen = self.strlen(position);
-        let mut string: Vec<u8> = self.packed_data[position..position + slen].to_vec();
+        buffer.clear();
+        buffer.extend_from_slice(&self.packed_data[position..position + slen]);
         //println!("block_index={} string_index={}, string={}", block_index, string_index, str::from_utf8(&string).unwrap());
         // loop takes around nearly half the time of the function
         for _ in 0..string_index {
@@ -216,18 +229,10 @@ impl DictSectPFC {
             let (delta, vbyte_bytes) = decode_vbyte_delta(&self.packed_data, position);
             position += vbyte_bytes;
             slen = self.strlen(position);
-            string.truncate(delta);
-            string.extend_from_slice(&self.packed_data[position..position + slen]);
-        }
-        // tried simdutf8::basic::from_utf8 but that didn't speed up extract that much
-        match str::from_utf8(&string) {
-            Ok(string) => Ok(String::from(string)),
-            Err(e) => Err(ExtractError::InvalidUtf8 {
-                source: e,
-                data: string.clone(),
-                recovered: String::from_utf8_lossy(&string).into_owned(),
-            }),
+            buffer.truncate(delta);
+            buffer.extend_from_slice(&self.packed_data[position..position + slen]);
         }
+        Ok(())
     }
 
     fn strlen(&self, offset: usize) -> usize {
@@ -245,7 +250,62 @@ impl DictSectPFC {
         self.num_strings
     }
 
+    /// Iterate over all strings of the section in ID order.
+    /// Decodes the packed data in a single pass instead of starting over at the block head for each ID like [`Self::extract`].
+    pub const fn iter(&self) -> DictSectPFCIter<'_> {
+        DictSectPFCIter { sect: self, i: 0, pos: 0, buffer: Vec::new() }
+    }
+
     pub fn read<R: BufRead>(reader: &mut R) -> io::Result<Self> {
+        let (num_strings, packed_length, block_size, sequence) = Self::read_head(reader)?;
+
+        // read packed data
+        let mut packed_data = vec![0u8; packed_length];
+        reader.read_exact(&mut packed_data)?;
+
+        // read packed data CRC32
+        let mut crc_code = [0_u8; 4];
+        reader.read_exact(&mut crc_code)?;
+        // validate packed data CRC32
+        // higher temporary memory usage but CRC can be calculated in parallel
+        // TODO can this be done without cloning?
+        let cloned_data = packed_data.clone();
+        let crc_handle = Some(Deferred::spawn(move || {
+            let crc_code = u32::from_le_bytes(crc_code);
+            let mut crc = CRCu32::crc32c();
+            crc.digest(&cloned_data[..]);
+            crc.get_crc() == crc_code
+        }));
+
+        Ok(DictSectPFC { num_strings, block_size, sequence, packed_data, crc_handle })
+    }
+
+    /// Parse a section from the bytes returned by [`Self::read_raw`].
+    /// The packed data stays in the given buffer instead of being copied and its CRC is calculated right away,
+    /// as the sections are already parsed in parallel.
+    pub(crate) fn from_raw(mut raw: Vec<u8>) -> io::Result<Self> {
+        use io::Error;
+        use io::ErrorKind::InvalidData;
+
+        let mut reader = raw.as_slice();
+        let (num_strings, packed_length, block_size, sequence) = Self::read_head(&mut reader)?;
+        let start = raw.len() - reader.len();
+        let end = start.saturating_add(packed_length);
+        let Some(&[a, b, c, d]) = raw.get(end..) else {
+            return Err(Error::new(InvalidData, "dictionary section length does not match its packed data"));
+        };
+        let mut crc = CRCu32::crc32c();
+        crc.digest(&raw[start..end]);
+        let crc_handle = Some(Deferred::Done(crc.get_crc() == u32::from_le_bytes([a, b, c, d])));
+        // shift the packed data to the front of the buffer in place
+        raw.truncate(end);
+        raw.drain(..start);
+        Ok(DictSectPFC { num_strings, block_size, sequence, packed_data: raw, crc_handle })
+    }
+
+    /// Read and validate the metadata and the sequence, returning the number of strings, the packed length,
+    /// the block size and the sequence.
+    fn read_head<R: BufRead>(reader: &mut R) -> io::Result<(usize, usize, usize, Sequence)> {
         use io::Error;
         use io::ErrorKind::InvalidData;
 
@@ -283,26 +343,201 @@ impl DictSectPFC {
 
         // read sequence log array
         let sequence = Sequence::read(reader)?;
+        Ok((num_strings, packed_length, block_size, sequence))
+    }
 
-        // read packed data
-        let mut packed_data = vec![0u8; packed_length];
-        reader.read_exact(&mut packed_data)?;
+    /// Read the bytes of a section without parsing or validating them, so that [`Self::from_raw`] can be run on
+    /// several sections in parallel.
+    /// Only the metadata is decoded to find out where the section ends.
+    pub fn read_raw<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+        use io::Error;
+        use io::ErrorKind::InvalidData;
 
-        // read packed data CRC32
-        let mut crc_code = [0_u8; 4];
-        reader.read_exact(&mut crc_code)?;
-        // validate packed data CRC32
-        // higher temporary memory usage but CRC can be calculated in parallel
-        // TODO can this be done without cloning?
-        let cloned_data = packed_data.clone();
-        let crc_handle = Some(thread::spawn(move || {
-            let crc_code = u32::from_le_bytes(crc_code);
-            let mut crc = CRCu32::crc32c();
-            crc.digest(&cloned_data[..]);
-            crc.get_crc() == crc_code
-        }));
+        // appends the next n bytes of the reader to the section
+        fn copy<R: BufRead>(reader: &mut R, raw: &mut Vec<u8>, n: usize) -> io::Result<()> {
+            let start = raw.len();
+            let end = start.checked_add(n).ok_or_else(|| Error::new(InvalidData, "section too large"))?;
+            raw.resize(end, 0);
+            reader.read_exact(&mut raw[start..])
+        }
+        // appends the next vbyte of the reader to the section and returns its value
+        fn copy_vbyte<R: BufRead>(reader: &mut R, raw: &mut Vec<u8>) -> io::Result<usize> {
+            let (n, bytes_read) = read_vbyte(reader)?;
+            raw.extend_from_slice(&bytes_read);
+            Ok(n)
+        }
 
-        Ok(DictSectPFC { num_strings, block_size, sequence, packed_data, crc_handle })
+        // preamble, number of strings, packed length, block size
+        let mut raw = Vec::new();
+        copy(reader, &mut raw, 1)?;
+        copy_vbyte(reader, &mut raw)?;
+        let packed_length = copy_vbyte(reader, &mut raw)?;
+        copy_vbyte(reader, &mut raw)?;
+        // section CRC8, sequence type, bits per entry, number of entries and sequence CRC8
+        copy(reader, &mut raw, 3)?;
+        let bits_per_entry = raw[raw.len() - 1];
+        let entries = copy_vbyte(reader, &mut raw)?;
+        copy(reader, &mut raw, 1)?;
+        // the sequence body is byte aligned at the end, followed by its CRC32
+        let body_length = usize::try_from((u64::from(bits_per_entry) * entries as u64).div_ceil(8))
+            .map_err(|_| Error::new(InvalidData, "sequence too large for this platform"))?;
+        copy(reader, &mut raw, body_length + 4)?;
+        // packed data and its CRC32
+        copy(reader, &mut raw, packed_length + 4)?;
+        Ok(raw)
+    }
+
+    /// Write the section including metadata and checksums in the format expected by [`Self::read`].
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut meta = vec![0x02_u8];
+        for n in [self.num_strings, self.packed_data.len(), self.block_size] {
+            meta.extend_from_slice(&encode_vbyte(n));
+        }
+        let mut crc = CRCu8::crc8();
+        crc.digest(&meta[..]);
+        meta.push(crc.get_crc());
+        w.write_all(&meta)?;
+        self.sequence.write(w)?;
+        w.write_all(&self.packed_data)?;
+        let mut crc = CRCu32::crc32c();
+        crc.digest(&self.packed_data[..]);
+        w.write_all(&crc.get_crc().to_le_bytes())
+    }
+}
+
+/// Builds a [`DictSectPFC`] in memory from strings that are added in ascending byte order.
+pub struct DictSectPFCBuilder {
+    block_size: usize,
+    num_strings: usize,
+    // start position of each block in the packed data
+    block_starts: Vec<usize>,
+    packed_data: Vec<u8>,
+    // last string, the next one is encoded as the length of the common prefix and the remaining suffix
+    previous: Vec<u8>,
+}
+
+impl DictSectPFCBuilder {
+    /// Number of strings per block used by hdt-cpp and hdt-java.
+    pub const DEFAULT_BLOCK_SIZE: usize = 16;
+
+    /// Start an empty section where each block contains the given number of strings.
+    pub const fn new(block_size: usize) -> Self {
+        DictSectPFCBuilder {
+            block_size,
+            num_strings: 0,
+            block_starts: Vec::new(),
+            packed_data: Vec::new(),
+            previous: Vec::new(),
+        }
+    }
+
+    /// Add the next string, which must be greater than the previous one.
+    pub fn push(&mut self, s: &[u8]) {
+        debug_assert!(self.num_strings == 0 || self.previous.as_slice() < s, "strings must be sorted and unique");
+        if self.num_strings % self.block_size == 0 {
+            // first string of a block is stored completely
+            self.block_starts.push(self.packed_data.len());
+            self.packed_data.extend_from_slice(s);
+        } else {
+            let delta = DictSectPFC::longest_common_prefix(&self.previous, s);
+            self.packed_data.extend_from_slice(&encode_vbyte(delta));
+            self.packed_data.extend_from_slice(&s[delta..]);
+        }
+        self.packed_data.push(0);
+        self.previous.clear();
+        self.previous.extend_from_slice(s);
+        self.num_strings += 1;
+    }
+
+    /// Number of strings added so far.
+    pub const fn len(&self) -> usize {
+        self.num_strings
+    }
+
+    /// Whether no string has been added yet.
+    pub const fn is_empty(&self) -> bool {
+        self.num_strings == 0
+    }
+
+    /// Finish the section. As in files written by hdt-cpp, the block sequence ends with the length of the packed data.
+    pub fn build(mut self) -> DictSectPFC {
+        self.block_starts.push(self.packed_data.len());
+        let bits = (usize::BITS - self.packed_data.len().leading_zeros()) as usize;
+        let sequence = Sequence::new(&self.block_starts, bits);
+        DictSectPFC {
+            num_strings: self.num_strings,
+            block_size: self.block_size,
+            sequence,
+            packed_data: self.packed_data,
+            // nothing to check for data that was not read from a file
+            crc_handle: Some(Deferred::Done(true)),
+        }
+    }
+}
+
+/// Iterator over the strings of a dictionary section, see [`DictSectPFC::iter`].
+pub struct DictSectPFCIter<'a> {
+    sect: &'a DictSectPFC,
+    // number of strings already decoded
+    i: usize,
+    // position of the next string in the packed data
+    pos: usize,
+    // current string, shares its prefix with the next one
+    buffer: Vec<u8>,
+}
+
+impl<'a> DictSectPFCIter<'a> {
+    /// Decode the next string and borrow its bytes until the next call.
+    /// Avoids the allocation of a new `String` for each entry.
+    pub fn next_bytes(&mut self) -> Option<&[u8]> {
+        let sect = self.sect;
+        if self.i >= sect.num_strings {
+            return None;
+        }
+        if self.i % sect.block_size == 0 {
+            // first string of a block is stored completely
+            self.pos = sect.sequence.get(self.i / sect.block_size);
+            self.buffer.clear();
+        } else {
+            let (delta, vbyte_bytes) = decode_vbyte_delta(&sect.packed_data, self.pos);
+            self.pos += vbyte_bytes;
+            self.buffer.truncate(delta);
+        }
+        let slen = sect.strlen(self.pos);
+        self.buffer.extend_from_slice(&sect.packed_data[self.pos..self.pos + slen]);
+        self.pos += slen + 1;
+        self.i += 1;
+        Some(&self.buffer)
+    }
+}
+
+impl<'a> Iterator for DictSectPFCIter<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.next_bytes()?;
+        match str::from_utf8(bytes) {
+            Ok(s) => Some(s.to_owned()),
+            Err(e) => {
+                let recovered = String::from_utf8_lossy(bytes).into_owned();
+                error!("Read invalid UTF-8 sequence, recovered: '{recovered}', caused by {e}");
+                Some(recovered)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.sect.num_strings - self.i;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> IntoIterator for &'a DictSectPFC {
+    type Item = String;
+    type IntoIter = DictSectPFCIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
@@ -314,7 +549,7 @@ mod tests {
     use crate::ControlInfo;
     use pretty_assertions::assert_eq;
     use std::fs::File;
-    use std::io::BufReader;
+    use std::io::{BufReader, Read, Seek, SeekFrom};
     /* unused
     #[test]
     fn test_decode() {
@@ -349,6 +584,12 @@ mod tests {
             let back = shared.extract(id).unwrap();
             assert_eq!(term, back, "term does not translate back to itself {} -> {} -> {}", term, id, back);
         }
+        let mut iter = shared.iter();
+        for id in 1..=shared.num_strings {
+            let s = iter.next().unwrap();
+            assert_eq!(shared.extract(id).unwrap(), s, "iter and extract differ at id {id}");
+        }
+        assert_eq!(None, iter.next());
         let sequence = shared.sequence;
         let data_size = (sequence.bits_per_entry * sequence.entries + 63) / 64;
         assert_eq!(sequence.data.len(), data_size);
@@ -364,8 +605,60 @@ mod tests {
             let back = subjects.extract(id).unwrap();
             assert_eq!(term, back, "term does not translate back to itself {} -> {} -> {}", term, id, back);
         }
+        let strings: Vec<String> = subjects.iter().collect();
+        assert_eq!(subjects.num_strings, strings.len());
+        for (i, s) in strings.iter().enumerate() {
+            assert_eq!(&subjects.extract(i + 1).unwrap(), s, "iter and extract differ at id {}", i + 1);
+        }
         let sequence = subjects.sequence;
         let data_size = (sequence.bits_per_entry * sequence.entries + 63) / 64;
         assert_eq!(sequence.data.len(), data_size);
     }
+
+    #[test]
+    fn build() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let mut reader = BufReader::new(file);
+        ControlInfo::read(&mut reader).unwrap();
+        Header::read(&mut reader).unwrap();
+        ControlInfo::read(&mut reader).unwrap();
+        for _ in 0..4 {
+            let start = reader.stream_position().unwrap();
+            let sect = DictSectPFC::read(&mut reader).unwrap();
+            let end = reader.stream_position().unwrap();
+            let mut builder = DictSectPFCBuilder::new(sect.block_size);
+            for s in &sect {
+                builder.push(s.as_bytes());
+            }
+            assert_eq!(sect.num_strings, builder.len());
+            let built = builder.build();
+            // written exactly as by hdt-cpp
+            let mut written = Vec::new();
+            built.write(&mut written).unwrap();
+            let mut original = vec![0; (end - start) as usize];
+            reader.seek(SeekFrom::Start(start)).unwrap();
+            reader.read_exact(&mut original).unwrap();
+            assert_eq!(original, written);
+            // parsing the raw bytes moves the packed data out of the buffer
+            let mut raw = DictSectPFC::from_raw(original.clone()).unwrap();
+            assert_eq!(sect.packed_data, raw.packed_data);
+            assert!(raw.crc_handle.take().unwrap().join().unwrap());
+            assert!(DictSectPFC::from_raw(original[..original.len() - 1].to_vec()).is_err());
+            // same encoding as hdt-cpp
+            assert_eq!(sect.packed_data, built.packed_data);
+            assert_eq!(
+                sect.sequence.into_iter().collect::<Vec<_>>(),
+                built.sequence.into_iter().collect::<Vec<_>>()
+            );
+            for id in 1..=sect.num_strings {
+                let s = sect.extract(id).unwrap();
+                assert_eq!(s, built.extract(id).unwrap());
+                assert_eq!(id, built.string_to_id(&s));
+            }
+        }
+        let empty = DictSectPFCBuilder::new(DictSectPFCBuilder::DEFAULT_BLOCK_SIZE).build();
+        assert_eq!(0, empty.num_strings());
+        assert_eq!(0, empty.string_to_id("http://example.org/a"));
+    }
 }
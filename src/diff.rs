@@ -0,0 +1,101 @@
+//! Subtract the triples of one HDT file from another without converting them to RDF text and back, similar to hdtDiff in hdt-java.
+use crate::builder::{build, object_len, position_terms, predicate_len, predicate_terms, subject_len};
+use crate::triples::{SubjectIter, TripleId};
+use crate::Hdt;
+
+/// Create a new HDT file with the triples of A that are not in B.
+/// The triples of A are scanned on the ID level and each one is looked up in B after translating its IDs,
+/// where the ID translation comes from a merge of the sorted dictionary sections of both files.
+/// The dictionary is then rebuilt from the terms that are still used, so terms that only occurred in removed triples are dropped
+/// and a shared term that is now only used as a subject or only as an object moves to the subject or object section.
+/// The resulting HDT has an empty header because the metadata of A doesn't apply to it.
+pub fn diff(a: &Hdt, b: &Hdt) -> Hdt {
+    let (da, db) = (&a.dict, &b.dict);
+    // for each old ID of A, the ID of the same term in the same position of B, 0 if B doesn't contain it there
+    let mut subject_map = vec![0; subject_len(da)];
+    let mut predicate_map = vec![0; predicate_len(da)];
+    let mut object_map = vec![0; object_len(da)];
+    position_terms([da, db], |_, [[sa, sb], [oa, ob]]| {
+        if sa != 0 {
+            subject_map[sa] = sb;
+        }
+        if oa != 0 {
+            object_map[oa] = ob;
+        }
+    });
+    predicate_terms([da, db], |_, [pa, pb]| {
+        if pa != 0 {
+            predicate_map[pa] = pb;
+        }
+    });
+    let keep = |t: &TripleId| {
+        let pat = TripleId::new(subject_map[t.subject_id], predicate_map[t.predicate_id], object_map[t.object_id]);
+        // a triple with a term that is missing in B can't be in B
+        pat.subject_id == 0
+            || pat.predicate_id == 0
+            || pat.object_id == 0
+            || SubjectIter::with_pattern(&b.triples, &pat).next().is_none()
+    };
+    build([a], [&keep])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{hdt, init, snikmeta, sorted};
+    use crate::{cat, IdKind};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn diff_small() {
+        let (x, y, z) = ("http://example.org/x", "http://example.org/y", "http://example.org/z");
+        let (p, p2) = ("http://example.org/p", "http://example.org/p2");
+        // x is shared in left, y is only used in the removed triple
+        let left = hdt(&[[x, p, y], [x, p2, z], [z, p2, x]]);
+        let right = hdt(&[[x, p, y], [z, p, x], [y, p2, "\"unrelated\""]]);
+        assert_eq!(2, left.dict.shared.num_strings());
+        let rest = diff(&left, &right);
+        let mut expected: Vec<[String; 3]> =
+            [[x, p2, z], [z, p2, x]].iter().map(|t| t.map(str::to_owned)).collect();
+        expected.sort();
+        assert_eq!(expected, sorted(&rest));
+        assert_eq!(2, rest.dict.shared.num_strings());
+        assert_eq!(1, rest.dict.predicates.num_strings());
+        assert_eq!(0, rest.dict.string_to_id(y, &IdKind::Object));
+        assert_eq!(0, rest.dict.string_to_id(p, &IdKind::Predicate));
+        // x moves from the shared to the subject section
+        let rest = diff(&left, &hdt(&[[z, p2, x]]));
+        assert_eq!(0, rest.dict.shared.num_strings());
+        assert_eq!(1, rest.dict.subjects.num_strings());
+        assert_eq!(2, rest.dict.objects.num_strings());
+        assert_eq!(1, rest.dict.string_to_id(x, &IdKind::Subject));
+        assert_eq!(0, rest.dict.string_to_id(x, &IdKind::Object));
+        assert_eq!(2, rest.triples_with_pattern(Some(x), None, None).count());
+        // B without any terms
+        assert_eq!(sorted(&left), sorted(&diff(&left, &hdt(&[]))));
+        // nothing left
+        let rest = diff(&left, &left);
+        assert_eq!(0, rest.triples().count());
+        assert_eq!(
+            0,
+            rest.dict.shared.num_strings() + rest.dict.subjects.num_strings() + rest.dict.objects.num_strings()
+        );
+    }
+
+    #[test]
+    fn diff_snikmeta() {
+        init();
+        let snik = snikmeta();
+        let extra =
+            hdt(&[["http://www.snik.eu/ontology/meta/Top", "http://example.org/p", "http://example.org/o"]]);
+        assert_eq!(0, diff(snik, snik).triples().count());
+        let same = diff(snik, &extra);
+        assert_eq!(sorted(snik), sorted(&same));
+        assert_eq!(snik.dict.shared.num_strings(), same.dict.shared.num_strings());
+        let merged = cat(snik, &extra);
+        let removed = diff(&merged, snik);
+        assert_eq!(sorted(&extra), sorted(&removed));
+        let restored = diff(&merged, &extra);
+        assert_eq!(sorted(snik), sorted(&restored));
+    }
+}
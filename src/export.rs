@@ -0,0 +1,435 @@
+//! Streaming export into N-Triples, N-Quads and Turtle.
+//! Terms are written directly from the bytes of the dictionary without parsing them into Sophia terms.
+use crate::hdt::{BufferedTriples, TranslateErr};
+use crate::triples::SubjectIter;
+use crate::Hdt;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+const RDF_TYPE: &[u8] = b"http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+fn invalid_data(e: TranslateErr) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Write an IRI in angle brackets, escaping characters that are not allowed in IRI references.
+fn write_iri<W: Write>(w: &mut W, iri: &[u8]) -> io::Result<()> {
+    w.write_all(b"<")?;
+    let mut start = 0;
+    for (i, &b) in iri.iter().enumerate() {
+        if b <= 0x20 || b"<>\"{}|^`\\".contains(&b) {
+            w.write_all(&iri[start..i])?;
+            write!(w, "\\u{b:04X}")?;
+            start = i + 1;
+        }
+    }
+    w.write_all(&iri[start..])?;
+    w.write_all(b">")
+}
+
+/// Write the lexical form of a literal, escaping quotes, backslashes and line breaks.
+fn write_lexical<W: Write>(w: &mut W, lex: &[u8]) -> io::Result<()> {
+    let mut start = 0;
+    for (i, &b) in lex.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            _ => continue,
+        };
+        w.write_all(&lex[start..i])?;
+        w.write_all(escaped)?;
+        start = i + 1;
+    }
+    w.write_all(&lex[start..])
+}
+
+/// Write a term in dictionary format in N-Triples syntax, which is also valid in N-Quads and Turtle.
+/// IRIs are stored without angle brackets and literals are stored as `"lexical form"` with an optional
+/// `@language` or `^^<datatype>` suffix, where the lexical form is not escaped.
+/// # Errors
+/// Returns any error of the writer.
+pub fn write_term<W: Write>(w: &mut W, term: &[u8]) -> io::Result<()> {
+    write_term_with(w, term, write_iri)
+}
+
+/// Like [`write_term`] but writes IRIs, including datatypes, with the given function.
+fn write_term_with<W: Write>(
+    w: &mut W, term: &[u8], write_iri: impl Fn(&mut W, &[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    match term.first() {
+        Some(b'"') => {
+            // the suffix cannot contain a quote, so the last quote ends the lexical form
+            let end = term.iter().rposition(|&b| b == b'"').filter(|&end| end > 0).unwrap_or(term.len());
+            w.write_all(b"\"")?;
+            write_lexical(w, &term[1..end])?;
+            w.write_all(b"\"")?;
+            let suffix = term.get(end + 1..).unwrap_or_default();
+            match suffix.strip_prefix(b"^^") {
+                Some(dt) => {
+                    w.write_all(b"^^")?;
+                    let dt = dt.strip_prefix(b"<").and_then(|dt| dt.strip_suffix(b">")).unwrap_or(dt);
+                    write_iri(w, dt)
+                }
+                None => w.write_all(suffix),
+            }
+        }
+        _ if term.starts_with(b"_:") => w.write_all(term),
+        _ => write_iri(w, term),
+    }
+}
+
+/// Namespace of an IRI up to and including the last `#` or `/`, `None` if the local name would be empty.
+fn namespace(iri: &[u8]) -> Option<&[u8]> {
+    let end = iri.iter().rposition(|&b| b == b'#' || b == b'/')? + 1;
+    // a scheme and authority such as "http://" alone is not a useful namespace
+    (end < iri.len() && !iri[..end].ends_with(b"//")).then_some(&iri[..end])
+}
+
+/// Whether the local part can be written after a prefix without escaping.
+/// This is a conservative subset of the Turtle `PN_LOCAL` production.
+fn is_local_name(local: &[u8]) -> bool {
+    let inner = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.');
+    match (local.first(), local.last()) {
+        (None, None) => true,
+        (Some(first), Some(last)) => {
+            (first.is_ascii_alphanumeric() || *first == b'_') && *last != b'.' && local.iter().all(inner)
+        }
+        _ => false,
+    }
+}
+
+/// Prefix name for a namespace derived from its last path segment, such as `meta` for `http://example.org/meta/`.
+fn prefix_name(namespace: &str) -> String {
+    let trimmed = namespace.trim_end_matches(['/', '#']);
+    let segment = trimmed.rsplit(['/', '#', '.', ':']).next().unwrap_or_default();
+    let name: String =
+        segment.chars().filter(char::is_ascii_alphanumeric).take(8).collect::<String>().to_lowercase();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        "ns".to_owned()
+    }
+}
+
+/// Prefixes that are declared by [`Hdt::write_turtle_with_prefixes`] unless they are overridden.
+pub const DEFAULT_PREFIXES: [(&str, &str); 4] = [
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// Number of namespaces that [`Hdt::write_turtle_with_prefixes`] detects in addition to the given ones.
+const DETECTED_PREFIXES: usize = 10;
+
+/// Prefix declarations of a Turtle document.
+#[derive(Debug, Default)]
+struct PrefixMap {
+    // pairs of name and namespace, in the order of declaration
+    prefixes: Vec<(String, String)>,
+    // indexes into prefixes with the longest namespace first, so that the most specific one is used
+    by_length: Vec<usize>,
+}
+
+impl PrefixMap {
+    /// Add a prefix unless its name or namespace is declared already.
+    fn add(&mut self, name: &str, namespace: &str) {
+        if self.prefixes.iter().any(|(n, ns)| n == name || ns == namespace) {
+            return;
+        }
+        self.prefixes.push((name.to_owned(), namespace.to_owned()));
+        self.by_length = (0..self.prefixes.len()).collect();
+        self.by_length.sort_by_key(|&i| std::cmp::Reverse(self.prefixes[i].1.len()));
+    }
+
+    /// Add a detected namespace with a unique name derived from it.
+    fn add_detected(&mut self, namespace: &str) {
+        let base = prefix_name(namespace);
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.prefixes.iter().any(|(existing, _)| *existing == name) {
+            name = format!("{base}{n}");
+            n += 1;
+        }
+        self.add(&name, namespace);
+    }
+
+    fn write_declarations<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for (name, namespace) in &self.prefixes {
+            write!(w, "@prefix {name}: ")?;
+            write_iri(w, namespace.as_bytes())?;
+            w.write_all(b" .\n")?;
+        }
+        if !self.prefixes.is_empty() {
+            w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write an IRI as a prefixed name if possible and in angle brackets otherwise.
+    fn write_iri<W: Write>(&self, w: &mut W, iri: &[u8]) -> io::Result<()> {
+        for &i in &self.by_length {
+            let (name, namespace) = &self.prefixes[i];
+            if let Some(local) = iri.strip_prefix(namespace.as_bytes()) {
+                if is_local_name(local) {
+                    write!(w, "{name}:")?;
+                    return w.write_all(local);
+                }
+            }
+        }
+        write_iri(w, iri)
+    }
+
+    fn write_term<W: Write>(&self, w: &mut W, term: &[u8]) -> io::Result<()> {
+        write_term_with(w, term, |w, iri| self.write_iri(w, iri))
+    }
+
+    /// Write a predicate, where `rdf:type` is abbreviated as `a`.
+    fn write_predicate<W: Write>(&self, w: &mut W, p: &[u8]) -> io::Result<()> {
+        if p == RDF_TYPE {
+            w.write_all(b"a")
+        } else {
+            self.write_term(w, p)
+        }
+    }
+}
+
+impl Hdt {
+    /// Write all triples as N-Triples in SPO order.
+    /// Wrap the writer in a [`std::io::BufWriter`] unless it is buffered already.
+    /// # Errors
+    /// Returns any error of the writer and [`io::ErrorKind::InvalidData`] if a triple cannot be translated.
+    pub fn write_ntriples<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_nquads(w, None)
+    }
+
+    /// Write all triples as N-Quads in SPO order, in the given named graph or else in the default graph.
+    /// The graph name is an IRI without angle brackets or a blank node starting with `_:`.
+    /// # Errors
+    /// Returns any error of the writer and [`io::ErrorKind::InvalidData`] if a triple cannot be translated.
+    pub fn write_nquads<W: Write>(&self, w: &mut W, graph: Option<&str>) -> io::Result<()> {
+        let mut triples = self.triples_buffered();
+        while let Some(triple) = triples.next_triple() {
+            let [s, p, o] = triple.map_err(invalid_data)?;
+            write_term(w, s)?;
+            w.write_all(b" ")?;
+            write_term(w, p)?;
+            w.write_all(b" ")?;
+            write_term(w, o)?;
+            if let Some(g) = graph {
+                w.write_all(b" ")?;
+                write_term(w, g.as_bytes())?;
+            }
+            w.write_all(b" .\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write all triples as Turtle, grouping the predicates of the same subject with `;`
+    /// and the objects of the same subject and predicate with `,`.
+    /// Terms are written in N-Triples syntax except for `rdf:type`, which is written as `a`.
+    /// # Errors
+    /// Returns any error of the writer and [`io::ErrorKind::InvalidData`] if a triple cannot be translated.
+    pub fn write_turtle<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_turtle_with(w, &PrefixMap::default())
+    }
+
+    /// Like [`Hdt::write_turtle`] but declares prefixes and writes IRIs as prefixed names where possible.
+    /// The given pairs of prefix name and namespace take precedence over the [`DEFAULT_PREFIXES`],
+    /// followed by the most frequent namespaces found by [`Hdt::detect_prefixes`].
+    /// # Errors
+    /// Returns any error of the writer and [`io::ErrorKind::InvalidData`] if a triple cannot be translated.
+    pub fn write_turtle_with_prefixes<W: Write>(&self, w: &mut W, prefixes: &[(&str, &str)]) -> io::Result<()> {
+        let mut map = PrefixMap::default();
+        for (name, namespace) in prefixes.iter().chain(&DEFAULT_PREFIXES) {
+            map.add(name, namespace);
+        }
+        for (_, namespace) in self.detect_prefixes(DETECTED_PREFIXES) {
+            map.add_detected(&namespace);
+        }
+        map.write_declarations(w)?;
+        self.write_turtle_with(w, &map)
+    }
+
+    /// The most frequent IRI namespaces of the predicate and shared sections with generated prefix names,
+    /// most frequent first. A namespace ends at the last `#` or `/` of an IRI and must occur at least twice.
+    /// Because the sections are sorted, IRIs with the same namespace mostly form contiguous runs,
+    /// so this takes a single pass over each section without decoding terms into strings.
+    pub fn detect_prefixes(&self, max: usize) -> Vec<(String, String)> {
+        let mut counts = HashMap::<Vec<u8>, usize>::new();
+        for sect in [&self.dict.predicates, &self.dict.shared] {
+            let mut iter = sect.iter();
+            // current run of IRIs with the same namespace
+            let mut run: Option<(Vec<u8>, usize)> = None;
+            while let Some(term) = iter.next_bytes() {
+                if term.starts_with(b"\"") || term.starts_with(b"_:") {
+                    continue;
+                }
+                let Some(ns) = namespace(term) else { continue };
+                match &mut run {
+                    Some((current, n)) if current.as_slice() == ns => *n += 1,
+                    _ => {
+                        if let Some((current, n)) = run.replace((ns.to_vec(), 1)) {
+                            *counts.entry(current).or_default() += n;
+                        }
+                    }
+                }
+            }
+            if let Some((current, n)) = run {
+                *counts.entry(current).or_default() += n;
+            }
+        }
+        let mut counts: Vec<_> = counts
+            .into_iter()
+            .filter(|(_, n)| *n >= 2)
+            .filter_map(|(ns, n)| Some((String::from_utf8(ns).ok()?, n)))
+            .collect();
+        // ties are broken by the namespace so that the result is deterministic
+        counts.sort_by(|(ns1, n1), (ns2, n2)| n2.cmp(n1).then_with(|| ns1.cmp(ns2)));
+        let mut map = PrefixMap::default();
+        for (namespace, _) in counts.into_iter().take(max) {
+            map.add_detected(&namespace);
+        }
+        map.prefixes
+    }
+
+    fn write_turtle_with<W: Write>(&self, w: &mut W, prefixes: &PrefixMap) -> io::Result<()> {
+        let mut triples = BufferedTriples::new(&self.dict, SubjectIter::new(&self.triples));
+        // subject and predicate ID of the previous triple, IDs start at 1
+        let mut last = (0, 0);
+        while let Some(triple) = triples.next_with_id() {
+            let (t, [s, p, o]) = triple.map_err(invalid_data)?;
+            if t.subject_id != last.0 {
+                if last.0 != 0 {
+                    w.write_all(b" .\n")?;
+                }
+                prefixes.write_term(w, s)?;
+                w.write_all(b" ")?;
+                prefixes.write_predicate(w, p)?;
+            } else if t.predicate_id != last.1 {
+                w.write_all(b" ;\n    ")?;
+                prefixes.write_predicate(w, p)?;
+            } else {
+                w.write_all(b" ,\n        ")?;
+                prefixes.write_term(w, o)?;
+                continue;
+            }
+            last = (t.subject_id, t.predicate_id);
+            w.write_all(b" ")?;
+            prefixes.write_term(w, o)?;
+        }
+        if last.0 != 0 {
+            w.write_all(b" .\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{init, snikmeta};
+    use pretty_assertions::assert_eq;
+
+    fn term(term: &str) -> String {
+        let mut v = Vec::new();
+        write_term(&mut v, term.as_bytes()).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!("<http://example.org/a>", term("http://example.org/a"));
+        assert_eq!("<http://example.org/a\\u0020b\\u003E>", term("http://example.org/a b>"));
+        assert_eq!("_:b0", term("_:b0"));
+        assert_eq!("\"plain\"", term("\"plain\""));
+        assert_eq!("\"a \\\"quoted\\\"\\nline\\\\\"@en", term("\"a \"quoted\"\nline\\\"@en"));
+        let int = "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>";
+        assert_eq!(int, term(int));
+        assert_eq!(int, term("\"1\"^^http://www.w3.org/2001/XMLSchema#integer"));
+    }
+
+    #[test]
+    fn ntriples_and_turtle() {
+        init();
+        let hdt = snikmeta();
+        let mut nt = Vec::new();
+        hdt.write_ntriples(&mut nt).unwrap();
+        let nt = String::from_utf8(nt).unwrap();
+        assert_eq!(327, nt.lines().count());
+        assert!(nt.lines().all(|line| line.ends_with(" .")));
+        let top = "<http://www.snik.eu/ontology/meta/Top> <http://www.w3.org/2000/01/rdf-schema#label> \"top class\"@en .";
+        assert!(nt.lines().any(|line| line == top));
+
+        let mut nq = Vec::new();
+        hdt.write_nquads(&mut nq, Some("http://example.org/g")).unwrap();
+        let nq = String::from_utf8(nq).unwrap();
+        assert_eq!(327, nq.lines().count());
+        assert!(nq.lines().all(|line| line.ends_with(" <http://example.org/g> .")));
+
+        let mut ttl = Vec::new();
+        hdt.write_turtle(&mut ttl).unwrap();
+        let ttl = String::from_utf8(ttl).unwrap();
+        let subjects = hdt.triples().map(|(s, _, _)| s).collect::<std::collections::BTreeSet<_>>().len();
+        // one statement per subject
+        assert_eq!(subjects, ttl.lines().filter(|line| line.ends_with(" .")).count());
+        assert_eq!(subjects, ttl.lines().filter(|line| !line.starts_with(' ')).count());
+        assert!(ttl.contains(" a <"));
+        assert!(ttl.lines().any(|line| line.ends_with(" ;")));
+    }
+
+    #[test]
+    fn prefixed_names() {
+        assert_eq!(Some(&b"http://example.org/ns#"[..]), namespace(b"http://example.org/ns#a"));
+        assert_eq!(None, namespace(b"http://example.org/"));
+        assert_eq!(None, namespace(b"http://example.org"));
+        assert!(is_local_name(b"Top"));
+        assert!(is_local_name(b"a.b-c_1"));
+        assert!(!is_local_name(b"a."));
+        assert!(!is_local_name(b"-a"));
+        assert!(!is_local_name(b"a/b"));
+        assert_eq!("meta", prefix_name("http://www.snik.eu/ontology/meta/"));
+        assert_eq!("ns", prefix_name("http://example.org/123/"));
+
+        let mut map = PrefixMap::default();
+        map.add("ex", "http://example.org/");
+        map.add("xsd", "http://www.w3.org/2001/XMLSchema#");
+        map.add_detected("http://example.org/ex/");
+        map.add("other", "http://example.org/");
+        assert_eq!(
+            vec!["ex", "xsd", "ex1"],
+            map.prefixes.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>()
+        );
+        let mut v = Vec::new();
+        for t in [
+            "http://example.org/ex/a", "http://example.org/b", "http://example.org/a b",
+            "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+        ] {
+            map.write_term(&mut v, t.as_bytes()).unwrap();
+            v.push(b' ');
+        }
+        assert_eq!("ex1:a ex:b <http://example.org/a\\u0020b> \"1\"^^xsd:integer ", String::from_utf8(v).unwrap());
+    }
+
+    #[test]
+    fn turtle_with_prefixes() {
+        init();
+        let hdt = snikmeta();
+        let meta = "http://www.snik.eu/ontology/meta/";
+        let detected = hdt.detect_prefixes(3);
+        assert!(detected.len() <= 3);
+        assert!(detected.contains(&("meta".to_owned(), meta.to_owned())), "{detected:?}");
+
+        let mut ttl = Vec::new();
+        hdt.write_turtle_with_prefixes(&mut ttl, &[("snik", meta)]).unwrap();
+        let ttl = String::from_utf8(ttl).unwrap();
+        assert!(ttl.starts_with(&format!("@prefix snik: <{meta}> .\n@prefix rdf: ")));
+        assert!(ttl.contains("@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n"));
+        // the user-supplied name replaces the detected one
+        assert!(!ttl.contains("@prefix meta:"));
+        assert!(ttl.contains("\nsnik:Top "));
+        assert!(ttl.contains(" rdfs:label \"top class\"@en"));
+    }
+}
@@ -1,14 +1,20 @@
-use crate::containers::ControlInfo;
+use crate::containers::{ControlInfo, ControlType};
 use crate::four_sect_dict::{DictErr, IdKind};
 use crate::header::Header;
-use crate::triples::{ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId, TriplesBitmap};
+use crate::triples::{Id, ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId, TriplesBitmap};
 use crate::FourSectDict;
 use bytesize::ByteSize;
 use log::{debug, error};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use self_cell::self_cell;
 //use mownstr::MownStr;
 use sophia::api::MownStr;
 use std::io;
+use std::io::Write;
 use std::iter;
+use std::ops::Range;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// In-memory representation of an RDF graph loaded from an HDT file.
@@ -16,14 +22,16 @@ use thiserror::Error;
 #[derive(Debug)]
 pub struct Hdt {
     //global_ci: ControlInfo,
-    //header: Header,
+    /// metadata about the dataset
+    pub header: Header,
     /// in-memory representation of dictionary
     pub dict: FourSectDict,
     /// in-memory representation of triples
     pub triples: TriplesBitmap,
 }
 
-type StringTriple<'a> = (MownStr<'a>, MownStr<'a>, MownStr<'a>);
+pub(crate) type StringTriple<'a> = (MownStr<'a>, MownStr<'a>, MownStr<'a>);
+type ArcTriple = (Arc<str>, Arc<str>, Arc<str>);
 
 /// The error type for the `translate_id` method.
 #[derive(Error, Debug)]
@@ -42,26 +50,42 @@ impl Hdt {
     /// The initial HDT specification at <http://www.w3.org/Submission/2011/03/> is outdated and not supported.
     pub fn new<R: std::io::BufRead>(mut reader: R) -> io::Result<Self> {
         ControlInfo::read(&mut reader)?;
-        Header::read(&mut reader)?;
+        let header = Header::read(&mut reader)?;
         let mut dict = FourSectDict::read(&mut reader)?;
         let triples = TriplesBitmap::read_sect(&mut reader)?;
         dict.validate()?;
-        let hdt = Hdt { dict, triples };
+        let hdt = Hdt { header, dict, triples };
         debug!("HDT size in memory {}, details:", ByteSize(hdt.size_in_bytes() as u64));
         debug!("{hdt:#?}");
         Ok(hdt)
     }
 
+    /// Creates an immutable HDT instance from the complete contents of an HDT file,
+    /// for example when it was fetched over the network in a browser without file system access.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::new(bytes)
+    }
+
+    /// Writes the HDT in the binary format read by [`Self::new`], for example the result of [`crate::cat`].
+    /// The header is written as N-Triples with the length calculated from its body.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        ControlInfo::new(ControlType::Global, "<http://purl.org/HDT/hdt#HDTv1>").write(w)?;
+        self.header.write(w)?;
+        self.dict.write(w)?;
+        self.triples.write(w)
+    }
+
     /// Recursive size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
         self.dict.size_in_bytes() + self.triples.size_in_bytes()
     }
 
-    /// Don't use this for many triples with shared values as you won't benefit from deduplication.
-    fn translate_id(&self, t: TripleId) -> Result<StringTriple, TranslateErr> {
-        let s = self.dict.id_to_string(t.subject_id, &IdKind::Subject).map_err(|e| TranslateErr { e, t })?;
-        let p = self.dict.id_to_string(t.predicate_id, &IdKind::Predicate).map_err(|e| TranslateErr { e, t })?;
-        let o = self.dict.id_to_string(t.object_id, &IdKind::Object).map_err(|e| TranslateErr { e, t })?;
+    /// Shared values are only decoded once if the term cache of the dictionary is enabled, see [`FourSectDict::set_cache`].
+    pub(crate) fn translate_id(&self, t: TripleId) -> Result<StringTriple<'static>, TranslateErr> {
+        let dict = &self.dict;
+        let s = dict.id_to_string_cached(t.subject_id, &IdKind::Subject).map_err(|e| TranslateErr { e, t })?;
+        let p = dict.id_to_string_cached(t.predicate_id, &IdKind::Predicate).map_err(|e| TranslateErr { e, t })?;
+        let o = dict.id_to_string_cached(t.object_id, &IdKind::Object).map_err(|e| TranslateErr { e, t })?;
         Ok((s.into(), p.into(), o.into()))
     }
 
@@ -69,11 +93,194 @@ impl Hdt {
     /// Using this method with a filter can be inefficient for large graphs,
     /// because the strings are stored in compressed form and must be decompressed and allocated.
     /// Whenever possible, use [`Hdt::triples_with_pattern`] instead.
+    /// Enable the term cache with [`FourSectDict::set_cache`] to decode repeated terms only once.
     pub fn triples(&self) -> impl Iterator<Item = StringTriple> + '_ {
-        // TODO deduplicate
         self.triples.into_iter().map(|id| self.translate_id(id).unwrap())
     }
 
+    /// Get all triples that fit the given triple pattern as shared strings, where `None` stands for a variable.
+    /// With the term cache of the dictionary enabled, frequent terms such as predicates and classes are decoded only once.
+    /// # Examples
+    /// ```no_run
+    /// # let file = std::fs::File::open("example.hdt").expect("error opening file");
+    /// let mut hdt = hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT");
+    /// hdt.dict.set_cache(10_000);
+    /// let types = hdt.triples_with_pattern_arc(None, Some("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"), None);
+    /// println!("{}", types.count());
+    /// println!("{:?}", hdt.dict.cache_stats());
+    /// ```
+    pub fn triples_with_pattern_arc(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> impl Iterator<Item = ArcTriple> + '_ {
+        self.triple_ids_with_pattern(sp, pp, op).filter_map(move |t| {
+            let translate = || -> Result<ArcTriple, TranslateErr> {
+                let s = self.dict.id_to_arc(t.subject_id, &IdKind::Subject).map_err(|e| TranslateErr { e, t })?;
+                let p =
+                    self.dict.id_to_arc(t.predicate_id, &IdKind::Predicate).map_err(|e| TranslateErr { e, t })?;
+                let o = self.dict.id_to_arc(t.object_id, &IdKind::Object).map_err(|e| TranslateErr { e, t })?;
+                Ok((s, p, o))
+            };
+            translate().map_err(|e| error!("{e}")).ok()
+        })
+    }
+
+    /// Like [`Hdt::triples_with_pattern`] but the iterator owns a reference-counted pointer to the HDT instead of borrowing it.
+    /// The iterator is `Send` and `'static`, so it can be handed to worker threads or async tasks.
+    /// # Examples
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # let file = std::fs::File::open("example.hdt").expect("error opening file");
+    /// let hdt = Arc::new(hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT"));
+    /// let leipzig = Arc::clone(&hdt).triples_with_pattern_owned(Some("http://dbpedia.org/resource/Leipzig"), None, None);
+    /// std::thread::spawn(move || println!("{:?}", leipzig.collect::<Vec<_>>()));
+    /// ```
+    pub fn triples_with_pattern_owned(
+        self: Arc<Self>, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> OwnedTriples {
+        OwnedTriples { ids: OwnedIds::new(self, |hdt| hdt.triple_ids_with_pattern(sp, pp, op)) }
+    }
+
+    /// Like [`Hdt::triples`] but translates into reusable buffers instead of allocating three strings per triple.
+    pub fn triples_buffered(&self) -> BufferedTriples<'_, SubjectIter<'_>> {
+        BufferedTriples::new(&self.dict, SubjectIter::new(&self.triples))
+    }
+
+    /// Like [`Hdt::triples_with_pattern`] but translates into reusable buffers instead of allocating three strings per triple.
+    pub fn triples_with_pattern_buffered(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> BufferedTriples<'_, Box<dyn Iterator<Item = TripleId> + Send + '_>> {
+        BufferedTriples::new(&self.dict, self.triple_ids_with_pattern(sp, pp, op))
+    }
+
+    /// Get the IDs of all triples that fit the given triple pattern, where `None` stands for a variable.
+    /// Use this over [`Hdt::triples_with_pattern`] if you don't need the strings.
+    pub fn triple_ids_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> Box<dyn Iterator<Item = TripleId> + Send + '_> {
+        match self.pattern_id(sp, pp, op) {
+            Some(pat) => self.triples.triples_with_pattern(&pat),
+            None => Box::new(iter::empty()),
+        }
+    }
+
+    /// Get all triples whose object ID is in the given range, optionally only those with the given predicate.
+    /// Intended for the literal ranges of the dictionary such as [`FourSectDict::lang_ranges`],
+    /// so that terms outside of the range are never decoded.
+    /// # Examples
+    /// ```no_run
+    /// # let file = std::fs::File::open("example.hdt").expect("error opening file");
+    /// let hdt = hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT");
+    /// let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+    /// for range in hdt.dict.lang_ranges("en") {
+    ///     hdt.triples_with_object_range(label, range).for_each(|t| println!("{t:?}"));
+    /// }
+    /// ```
+    pub fn triples_with_object_range(
+        &self, pp: Option<&str>, ids: Range<Id>,
+    ) -> impl Iterator<Item = StringTriple<'static>> + '_ {
+        self.triple_ids_with_object_range(pp, ids)
+            .filter_map(move |t| self.translate_id(t).map_err(|e| error!("{e}")).ok())
+    }
+
+    /// Get the IDs of all triples whose object ID is in the given range, optionally only those with the given predicate.
+    /// Each object is looked up separately, with [`ObjectIter`] or, given a predicate, with [`PredicateObjectIter`].
+    pub fn triple_ids_with_object_range(
+        &self, pp: Option<&str>, ids: Range<Id>,
+    ) -> Box<dyn Iterator<Item = TripleId> + Send + '_> {
+        let p = pp.map_or(0, |p| self.dict.string_to_id(p, &IdKind::Predicate));
+        if pp.is_some() && p == 0 {
+            return Box::new(iter::empty());
+        }
+        let objects = self.dict.shared.num_strings() + self.dict.objects.num_strings();
+        let ids = ids.start.max(1)..ids.end.min(objects + 1);
+        if p == 0 {
+            Box::new(ids.flat_map(move |o| ObjectIter::new(&self.triples, o)))
+        } else {
+            Box::new(ids.flat_map(move |o| {
+                PredicateObjectIter::new(&self.triples, p, o).map(move |s| TripleId::new(s, p, o))
+            }))
+        }
+    }
+
+    /// Number of triples that fit the given triple pattern, where `None` stands for a variable.
+    /// Faster than counting the results of [`Hdt::triples_with_pattern`] because no strings are decoded.
+    /// For ??? and ??O, the number is looked up directly without iterating.
+    pub fn pattern_count(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> usize {
+        let op_index = &self.triples.op_index;
+        match self.pattern_id(sp, pp, op) {
+            None => 0,
+            Some(TripleId { subject_id: 0, predicate_id: 0, object_id: 0 }) => {
+                self.triples.adjlist_z.sequence.entries
+            }
+            Some(TripleId { subject_id: 0, predicate_id: 0, object_id: o }) => {
+                op_index.last(o) - op_index.find(o) + 1
+            }
+            Some(pat) => self.triples.triples_with_pattern(&pat).count(),
+        }
+    }
+
+    /// Translate a string triple pattern into an ID triple pattern, where `None` and 0 stand for a variable.
+    /// Returns `None` if at least one term does not exist in the graph.
+    pub(crate) fn pattern_id(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> Option<TripleId> {
+        let s = sp.map_or(Some(0), |s| Some(self.dict.string_to_id(s, &IdKind::Subject)).filter(|&id| id != 0))?;
+        let p =
+            pp.map_or(Some(0), |p| Some(self.dict.string_to_id(p, &IdKind::Predicate)).filter(|&id| id != 0))?;
+        let o = op.map_or(Some(0), |o| Some(self.dict.string_to_id(o, &IdKind::Object)).filter(|&id| id != 0))?;
+        Some(TripleId::new(s, p, o))
+    }
+
+    /// Parallel iterator over all triples as strings, which does not preserve the order.
+    /// The triples are split into blocks of subjects that are translated in different threads.
+    #[cfg(feature = "rayon")]
+    pub fn par_triples(&self) -> impl ParallelIterator<Item = StringTriple<'static>> + '_ {
+        self.par_triples_with_pattern(None, None, None)
+    }
+
+    /// Parallel version of [`Hdt::triples_with_pattern`], which does not preserve the order.
+    /// See [`Hdt::par_triple_ids_with_pattern`] for which patterns are split among multiple threads.
+    #[cfg(feature = "rayon")]
+    pub fn par_triples_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> impl ParallelIterator<Item = StringTriple<'static>> + '_ {
+        self.par_triple_ids_with_pattern(sp, pp, op)
+            .filter_map(|t| self.translate_id(t).map_err(|e| error!("{e}")).ok())
+    }
+
+    /// Parallel version of [`Hdt::triple_ids_with_pattern`], which does not preserve the order.
+    /// The ??? pattern is split into blocks of subjects and the ?P? pattern into chunks of occurrences of the property.
+    /// Other patterns are processed as a single chunk, because they are usually selective enough.
+    #[cfg(feature = "rayon")]
+    pub fn par_triple_ids_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> impl ParallelIterator<Item = TripleId> + '_ {
+        /// Split the given number of elements into ranges so that each thread gets several chunks for load balancing.
+        fn chunks(len: usize) -> impl Iterator<Item = (usize, usize)> {
+            let size = len.div_ceil(rayon::current_num_threads() * 8).max(1);
+            (0..len).step_by(size).map(move |start| (start, (start + size).min(len)))
+        }
+        type Chunk<'a> = Box<dyn Iterator<Item = TripleId> + Send + 'a>;
+
+        let triples = &self.triples;
+        let parts: Vec<Chunk> = match self.pattern_id(sp, pp, op) {
+            None => Vec::new(),
+            Some(pat) => match (pat.subject_id, pat.predicate_id, pat.object_id) {
+                (0, 0, 0) => {
+                    let num_subjects = self.dict.shared.num_strings() + self.dict.subjects.num_strings();
+                    chunks(num_subjects)
+                        .map(|(start, end)| {
+                            Box::new(SubjectIter::with_subject_range(triples, start + 1, end + 1)) as Chunk
+                        })
+                        .collect()
+                }
+                (0, p, 0) => chunks(PredicateIter::occurrences(triples, p))
+                    .map(|(start, end)| Box::new(PredicateIter::with_range(triples, p, start, end)) as Chunk)
+                    .collect(),
+                _ => vec![triples.triples_with_pattern(&pat)],
+            },
+        };
+        parts.into_par_iter().flat_map_iter(|part| part)
+    }
+
     /// Get all objects with the given subject and property.
     pub fn objects_with_sp(&self, s: &str, p: &str) -> Box<dyn Iterator<Item = String> + '_> {
         let sid = self.dict.string_to_id(s, &IdKind::Subject);
@@ -190,22 +397,112 @@ impl Hdt {
     }
 }
 
+type Ids<'a> = Box<dyn Iterator<Item = TripleId> + Send + 'a>;
+
+self_cell!(
+    // triple ID iterator together with the HDT it borrows from
+    struct OwnedIds {
+        owner: Arc<Hdt>,
+        #[covariant]
+        dependent: Ids,
+    }
+);
+
+/// Iterator over string triples that keeps the HDT alive, see [`Hdt::triples_with_pattern_owned`].
+pub struct OwnedTriples {
+    ids: OwnedIds,
+}
+
+impl OwnedTriples {
+    /// The HDT that this iterator queries.
+    pub fn hdt(&self) -> &Arc<Hdt> {
+        self.ids.borrow_owner()
+    }
+
+    /// Advance the iterator and return the next triple ID without translating it to strings.
+    pub fn next_id(&mut self) -> Option<TripleId> {
+        self.ids.with_dependent_mut(|_, ids| ids.next())
+    }
+}
+
+impl Iterator for OwnedTriples {
+    type Item = StringTriple<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ids.with_dependent_mut(|hdt, ids| loop {
+            let t = ids.next()?;
+            match hdt.translate_id(t) {
+                Ok(triple) => return Some(triple),
+                Err(e) => error!("{e}"),
+            }
+        })
+    }
+}
+
+/// Translates triple IDs into strings that are stored in reusable buffers.
+/// This is not an [`Iterator`] because each triple borrows the buffers until the next call.
+/// Components that are equal to the ones of the previous triple, such as the subject in an S?? pattern, are not decoded again.
+/// # Examples
+/// ```no_run
+/// # let file = std::fs::File::open("example.hdt").expect("error opening file");
+/// # let hdt = hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT");
+/// let mut triples = hdt.triples_buffered();
+/// while let Some(Ok([s, p, o])) = triples.next_triple() {
+///     println!("{} {} {}", String::from_utf8_lossy(s), String::from_utf8_lossy(p), String::from_utf8_lossy(o));
+/// }
+/// ```
+pub struct BufferedTriples<'a, I> {
+    dict: &'a FourSectDict,
+    ids: I,
+    // IDs whose strings are currently in the buffers, 0 if the buffer is invalid
+    last: [Id; 3],
+    buffers: [Vec<u8>; 3],
+}
+
+impl<'a, I: Iterator<Item = TripleId>> BufferedTriples<'a, I> {
+    /// Translate the given triple IDs using the given dictionary.
+    pub const fn new(dict: &'a FourSectDict, ids: I) -> Self {
+        BufferedTriples { dict, ids, last: [0; 3], buffers: [Vec::new(), Vec::new(), Vec::new()] }
+    }
+
+    /// Translate the next triple and borrow the bytes of its subject, predicate and object until the next call.
+    pub fn next_triple(&mut self) -> Option<Result<[&[u8]; 3], TranslateErr>> {
+        self.next_with_id().map(|r| r.map(|(_, terms)| terms))
+    }
+
+    /// Like [`BufferedTriples::next_triple`] but also returns the triple ID, for example to group triples by subject.
+    pub fn next_with_id(&mut self) -> Option<Result<(TripleId, [&[u8]; 3]), TranslateErr>> {
+        const KINDS: [&IdKind; 3] = [&IdKind::Subject, &IdKind::Predicate, &IdKind::Object];
+        let t = self.ids.next()?;
+        let ids = [t.subject_id, t.predicate_id, t.object_id];
+        for i in 0..3 {
+            if ids[i] != self.last[i] {
+                self.last[i] = 0;
+                if let Err(e) = self.dict.id_to_bytes(ids[i], KINDS[i], &mut self.buffers[i]) {
+                    return Some(Err(TranslateErr { e, t }));
+                }
+                self.last[i] = ids[i];
+            }
+        }
+        let [s, p, o] = &self.buffers;
+        Some(Ok((t, [s.as_slice(), p.as_slice(), o.as_slice()])))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::init;
+    use crate::tests::{init, load_snikmeta, snikmeta};
     use pretty_assertions::{assert_eq, assert_ne};
-    use std::fs::File;
 
     #[test]
     fn triples() {
         init();
-        let filename = "tests/resources/snikmeta.hdt";
-        let file = File::open(filename).expect("error opening file");
-        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let hdt = snikmeta();
         let triples = hdt.triples();
         let v: Vec<StringTriple> = triples.collect();
         assert_eq!(v.len(), 327);
+        assert!(!hdt.header.body.is_empty());
         assert_eq!(v, hdt.triples_with_pattern(None, None, None).collect::<Vec<_>>(), "all triples not equal ???");
         assert_ne!(0, hdt.dict.string_to_id("http://www.snik.eu/ontology/meta", &IdKind::Subject));
         for uri in ["http://www.snik.eu/ontology/meta/Top", "http://www.snik.eu/ontology/meta", "doesnotexist"] {
@@ -250,4 +547,168 @@ mod tests {
             "S?O multiple"
         );
     }
+
+    #[test]
+    fn pattern_count() {
+        init();
+        let hdt = snikmeta();
+        assert_eq!(327, hdt.pattern_count(None, None, None));
+        let meta = "http://www.snik.eu/ontology/meta";
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let et = "http://www.snik.eu/ontology/meta/EntityType";
+        for (sp, pp, op) in [
+            (Some(meta), None, None),
+            (None, Some(label), None),
+            (None, None, Some(et)),
+            (None, Some("http://www.w3.org/2000/01/rdf-schema#subClassOf"), Some(et)),
+            (None, None, Some("doesnotexist")),
+        ] {
+            assert_eq!(
+                hdt.triples_with_pattern(sp, pp, op).count(),
+                hdt.pattern_count(sp, pp, op),
+                "{sp:?} {pp:?} {op:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn triples_buffered() {
+        init();
+        let hdt = snikmeta();
+        let mut buffered = hdt.triples_buffered();
+        for (s, p, o) in hdt.triples() {
+            let [bs, bp, bo] = buffered.next_triple().unwrap().unwrap();
+            assert_eq!([s.as_bytes(), p.as_bytes(), o.as_bytes()], [bs, bp, bo]);
+        }
+        assert!(buffered.next_triple().is_none());
+
+        let meta = "http://www.snik.eu/ontology/meta";
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let et = "http://www.snik.eu/ontology/meta/EntityType";
+        for (sp, pp, op) in [(Some(meta), None, None), (None, Some(label), None), (None, None, Some(et))] {
+            let expected: Vec<_> = hdt.triples_with_pattern(sp, pp, op).collect();
+            assert_eq!(expected.len(), hdt.triple_ids_with_pattern(sp, pp, op).count());
+            let mut buffered = hdt.triples_with_pattern_buffered(sp, pp, op);
+            for (s, p, o) in expected {
+                let [bs, bp, bo] = buffered.next_triple().unwrap().unwrap();
+                assert_eq!([s.as_bytes(), p.as_bytes(), o.as_bytes()], [bs, bp, bo]);
+            }
+            assert!(buffered.next_triple().is_none());
+        }
+        assert_eq!(0, hdt.triple_ids_with_pattern(Some("doesnotexist"), None, None).count());
+    }
+
+    #[test]
+    fn triples_with_pattern_arc() {
+        init();
+        let mut hdt = load_snikmeta();
+        hdt.dict.set_cache(100);
+        let expected: Vec<_> =
+            hdt.triples().map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string())).collect();
+        let actual: Vec<_> = hdt
+            .triples_with_pattern_arc(None, None, None)
+            .map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string()))
+            .collect();
+        assert_eq!(expected, actual);
+        let stats = hdt.dict.cache_stats().unwrap();
+        // both triples and triples_with_pattern_arc look up each term in the cache
+        assert_eq!(6 * expected.len() as u64, stats.hits + stats.misses);
+        assert!(stats.hits > stats.misses, "{stats:?}");
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn thread_safety() {
+        init();
+        // compile-time checks
+        assert_send_sync::<Hdt>();
+        assert_send_sync::<FourSectDict>();
+        assert_send_sync::<TriplesBitmap>();
+        assert_send_sync::<crate::HdtGraph>();
+
+        let hdt = Arc::new(load_snikmeta());
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let expected: Vec<_> = hdt.triples_with_pattern(None, Some(label), None).collect();
+        let owned = Arc::clone(&hdt).triples_with_pattern_owned(None, Some(label), None);
+        let actual = std::thread::spawn(move || owned.collect::<Vec<_>>()).join().unwrap();
+        assert_eq!(expected, actual);
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let hdt = Arc::clone(&hdt);
+                std::thread::spawn(move || hdt.triples_with_pattern(None, Some(label), None).count())
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(expected.len(), worker.join().unwrap());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_triples() {
+        init();
+        let hdt = snikmeta();
+        let to_string = |(s, p, o): StringTriple| (s.to_string(), p.to_string(), o.to_string());
+        let meta = "http://www.snik.eu/ontology/meta";
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        for (sp, pp, op) in [
+            (None, None, None),
+            (None, Some(label), None),
+            (Some(meta), None, None),
+            (Some("doesnotexist"), None, None),
+        ] {
+            let mut expected: Vec<_> = hdt.triples_with_pattern(sp, pp, op).map(to_string).collect();
+            let mut actual: Vec<_> = hdt.par_triples_with_pattern(sp, pp, op).map(to_string).collect();
+            expected.sort_unstable();
+            actual.sort_unstable();
+            assert_eq!(expected, actual, "{sp:?} {pp:?} {op:?}");
+        }
+        assert_eq!(327, hdt.par_triples().count());
+    }
+
+    #[test]
+    fn write() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        let hdt = Hdt::from_bytes(&original).unwrap();
+        let mut written = Vec::new();
+        hdt.write(&mut written).unwrap();
+        let reloaded = Hdt::from_bytes(&written).unwrap();
+        assert_eq!(hdt.header.body, reloaded.header.body);
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), reloaded.triples().collect::<Vec<_>>());
+        // the header is serialized differently but dictionary and triples are unchanged
+        let dict_start = |bytes: &[u8]| {
+            let format = b"<http://purl.org/HDT/hdt#dictionaryFour>";
+            bytes.windows(format.len()).position(|w| w == format).unwrap() - b"$HDT".len() - 1
+        };
+        assert_eq!(original[dict_start(&original)..], written[dict_start(&written)..]);
+    }
+
+    #[test]
+    fn triples_with_object_range() {
+        init();
+        let hdt = snikmeta();
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let mut expected: Vec<_> =
+            hdt.triples_with_pattern(None, Some(label), None).filter(|t| t.2.ends_with("\"@en")).collect();
+        expected.sort();
+        assert!(!expected.is_empty());
+        let mut english: Vec<_> = hdt
+            .dict
+            .lang_ranges("en")
+            .into_iter()
+            .flat_map(|range| hdt.triples_with_object_range(Some(label), range))
+            .collect();
+        english.sort();
+        assert_eq!(expected, english);
+        let literals = hdt.dict.literal_range();
+        let count = hdt.triples().filter(|t| t.2.starts_with('"')).count();
+        assert_eq!(count, hdt.triples_with_object_range(None, literals.clone()).count());
+        assert_eq!(0, hdt.triples_with_object_range(Some("doesnotexist"), literals).count());
+        assert_eq!(
+            hdt.pattern_count(None, None, None),
+            hdt.triple_ids_with_object_range(None, 0..usize::MAX).count()
+        );
+    }
 }
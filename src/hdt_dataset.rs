@@ -0,0 +1,182 @@
+//! Sophia dataset of several HDT files, each one a named graph.
+use crate::hdt::Hdt;
+use crate::hdt_graph::{term_string, HdtGraph};
+use sophia::api::dataset::{DQuadSource, Dataset};
+use sophia::api::graph::Graph;
+use sophia::api::quad::Spog;
+use sophia::api::term::matcher::{GraphNameMatcher, TermMatcher};
+use sophia::api::term::{BnodeId, IriRef, SimpleTerm, Term};
+use sophia::api::MownStr;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io::{self, Error, ErrorKind};
+use std::rc::Rc;
+
+/// RDF dataset that maps graph names to HDT files, for example to load several files into a Sophia based tool.
+/// Graph names are IRIs or blank nodes in the format of [`Hdt::triples_with_pattern`], where `None` is the default graph.
+/// Quad patterns are answered by the [`HdtGraph::triples_matching`] of each graph whose name fits.
+/// # Examples
+/// ```no_run
+/// use sophia::api::dataset::Dataset;
+/// let mut dataset = hdt::HdtDataset::new();
+/// for (name, path) in [("http://example.org/a", "a.hdt"), ("http://example.org/b", "b.hdt")] {
+///     let file = std::fs::File::open(path).expect("error opening file");
+///     let hdt = hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT");
+///     dataset.insert(Some(name), hdt).expect("invalid graph name");
+/// }
+/// println!("{}", dataset.quads().count());
+/// ```
+#[derive(Debug, Default)]
+pub struct HdtDataset {
+    graphs: BTreeMap<Option<String>, HdtGraph>,
+}
+
+impl HdtDataset {
+    /// Dataset without any graphs.
+    pub const fn new() -> Self {
+        HdtDataset { graphs: BTreeMap::new() }
+    }
+
+    /// Add the HDT as the graph with the given name, `None` for the default graph.
+    /// Returns the graph that was replaced, if any, or an error if the name is neither an IRI nor a blank node.
+    pub fn insert(&mut self, name: Option<&str>, hdt: Hdt) -> io::Result<Option<HdtGraph>> {
+        if let Some(name) = name {
+            let valid = match name.strip_prefix("_:") {
+                Some(id) => BnodeId::new(id).is_ok(),
+                // the empty string is a valid relative IRI reference
+                None => !name.is_empty() && IriRef::new(name).is_ok(),
+            };
+            if !valid {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("invalid graph name {name}")));
+            }
+        }
+        Ok(self.graphs.insert(name.map(str::to_owned), HdtGraph::new(hdt)))
+    }
+
+    /// Remove the graph with the given name, `None` for the default graph.
+    pub fn remove(&mut self, name: Option<&str>) -> Option<HdtGraph> {
+        self.graphs.remove(&name.map(str::to_owned))
+    }
+
+    /// The graph with the given name, `None` for the default graph.
+    pub fn graph(&self, name: Option<&str>) -> Option<&HdtGraph> {
+        self.graphs.get(&name.map(str::to_owned))
+    }
+
+    /// Names of the graphs in ascending order, starting with `None` if there is a default graph.
+    pub fn graph_names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.graphs.keys().map(Option::as_deref)
+    }
+
+    /// Recursive size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.graphs.values().map(HdtGraph::size_in_bytes).sum()
+    }
+}
+
+// graph name in the format of the HDT as a Sophia term, names are checked by insert
+fn graph_term(name: Option<&str>) -> Option<SimpleTerm<'_>> {
+    name.map(|name| match name.strip_prefix("_:") {
+        Some(id) => SimpleTerm::BlankNode(BnodeId::new_unchecked(MownStr::from_str(id))),
+        None => SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from_str(name))),
+    })
+}
+
+/// Matcher that is shared between the graphs of a dataset, because [`Graph::triples_matching`] takes it by value.
+struct Shared<M>(Rc<M>);
+
+impl<M: TermMatcher> TermMatcher for Shared<M> {
+    type Term = M::Term;
+
+    fn matches<T2: Term + ?Sized>(&self, term: &T2) -> bool {
+        self.0.matches(term)
+    }
+
+    fn constant(&self) -> Option<&Self::Term> {
+        self.0.constant()
+    }
+}
+
+impl Dataset for HdtDataset {
+    type Quad<'a> = Spog<SimpleTerm<'a>>;
+    type Error = Infallible;
+
+    fn quads(&self) -> DQuadSource<Self> {
+        Box::new(self.graphs.iter().flat_map(|(name, graph)| {
+            let g = graph_term(name.as_deref());
+            graph.triples().map(move |t| t.map(|t| (t, g.clone())))
+        }))
+    }
+
+    /// Constant graph name matchers select a single graph, other ones are checked against each graph name.
+    /// The term matchers are passed on to [`HdtGraph::triples_matching`].
+    fn quads_matching<'s, S, P, O, G>(&'s self, sm: S, pm: P, om: O, gm: G) -> DQuadSource<'s, Self>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+        G: GraphNameMatcher + 's,
+    {
+        let graphs: Vec<_> = match gm.constant() {
+            Some(name) => {
+                let name = name.map(|g| term_string(&g.as_simple()));
+                self.graphs.get_key_value(&name).into_iter().collect()
+            }
+            None => {
+                self.graphs.iter().filter(|(name, _)| gm.matches(graph_term(name.as_deref()).as_ref())).collect()
+            }
+        };
+        let (sm, pm, om) = (Rc::new(sm), Rc::new(pm), Rc::new(om));
+        Box::new(graphs.into_iter().flat_map(move |(name, graph)| {
+            let g = graph_term(name.as_deref());
+            graph
+                .triples_matching(Shared(sm.clone()), Shared(pm.clone()), Shared(om.clone()))
+                .map(move |t| t.map(|t| (t, g.clone())))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{hdt, init, load_snikmeta};
+    use pretty_assertions::assert_eq;
+    use sophia::api::term::matcher::Any;
+    use sophia::api::term::IriRef;
+
+    #[test]
+    fn dataset() {
+        init();
+        let top = "http://www.snik.eu/ontology/meta/Top";
+        let (p, o) = ("http://example.org/p", "http://example.org/o");
+        let (g1, g2) = ("http://example.org/g1", "_:g2");
+        let mut dataset = HdtDataset::new();
+        assert!(dataset.insert(None, load_snikmeta()).unwrap().is_none());
+        dataset.insert(Some(g1), hdt(&[[top, p, o], [o, p, top]])).unwrap();
+        dataset.insert(Some(g2), hdt(&[[top, p, o]])).unwrap();
+        for invalid in ["", "\"literal\"", "_:", "not an iri"] {
+            let err = dataset.insert(Some(invalid), hdt(&[[top, p, o]])).unwrap_err();
+            assert_eq!(ErrorKind::InvalidInput, err.kind(), "{invalid}");
+        }
+        assert_eq!(vec![None, Some(g2), Some(g1)], dataset.graph_names().collect::<Vec<_>>());
+        assert_eq!(330, dataset.quads().count());
+
+        let s = SimpleTerm::Iri(IriRef::new_unchecked(top.into()));
+        let pt = SimpleTerm::Iri(IriRef::new_unchecked(p.into()));
+        let g = SimpleTerm::Iri(IriRef::new_unchecked(g1.into()));
+        let in_g1: Vec<_> = dataset.quads_matching(Any, Any, Any, Some(Some(&g))).map(Result::unwrap).collect();
+        assert_eq!(2, in_g1.len());
+        assert!(in_g1.iter().all(|(_, name)| name.as_ref() == Some(&g)));
+        assert_eq!(327, dataset.quads_matching(Any, Any, Any, Some(None::<&SimpleTerm>)).count());
+        assert_eq!(2, dataset.quads_matching(Some(&s), Some(&pt), Any, Any).count());
+        let default_top = dataset.graph(None).unwrap().triples_matching(Some(&s), Any, Any).count();
+        assert_eq!(default_top + 2, dataset.quads_matching(Some(&s), Any, Any, Any).count());
+        // several graph names, one of them a blank node
+        let b = graph_term(Some(g2)).unwrap();
+        assert_eq!(3, dataset.quads_matching(Any, Any, Any, [Some(g.clone()), Some(b)]).count());
+        assert_eq!(0, dataset.quads_matching(Any, Any, Any, Some(Some(&pt))).count());
+
+        assert!(dataset.remove(None).is_some());
+        assert_eq!(3, dataset.quads().count());
+    }
+}
@@ -1,43 +1,98 @@
 // //! *This module is available only if HDT is built with the `"sophia"` feature.*
 //#[cfg(feature = "sophia")]
 use crate::four_sect_dict::IdKind;
-use crate::hdt::Hdt;
-use crate::triples::{Id, ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId};
-use log::debug;
+use crate::hdt::{Hdt, StringTriple};
+use crate::triples::{Id, TripleId};
+use crate::DictSectPFC;
+use log::{debug, error};
 
-use sophia::api::graph::{GTripleSource, Graph};
+use sophia::api::graph::{GResult, GTermSource, GTripleSource, Graph};
 //use mownstr::MownStr;
 use sophia::api::MownStr;
 
 use sophia::api::term::FromTerm;
-use sophia::api::term::{matcher::TermMatcher, BnodeId, IriRef, LanguageTag, SimpleTerm, Term};
+use sophia::api::term::{matcher::Any, matcher::TermMatcher, BnodeId, IriRef, LanguageTag, SimpleTerm, Term};
 use std::convert::Infallible;
 use std::io::{self, Error, ErrorKind};
 use std::iter;
+use std::sync::OnceLock;
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
 
 /// Adapter to use HDT as a Sophia graph.
+#[derive(Debug)]
 pub struct HdtGraph {
     hdt: Hdt,
+    // decoded on first use, there are few predicates and they repeat in most triples so their terms borrow from here
+    predicates: OnceLock<Vec<Option<Box<str>>>>,
 }
 
 impl HdtGraph {
     /// Wrapper around Hdt.
     pub const fn new(hdt: Hdt) -> Self {
-        HdtGraph { hdt }
+        HdtGraph { hdt, predicates: OnceLock::new() }
     }
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
-        self.hdt.size_in_bytes()
+        let predicates = self.predicates.get().map_or(0, |p| p.iter().flatten().map(|p| p.len()).sum::<usize>());
+        self.hdt.size_in_bytes() + predicates
+    }
+
+    fn predicate_strings(&self) -> &[Option<Box<str>>] {
+        self.predicates.get_or_init(|| {
+            (1..=self.hdt.dict.predicates.num_strings())
+                .map(|id| {
+                    let p = self.hdt.dict.id_to_string(id, &IdKind::Predicate);
+                    p.map(String::into_boxed_str).map_err(|e| error!("{e}")).ok()
+                })
+                .collect()
+        })
+    }
+
+    /// Sophia term for the given ID, `None` if it cannot be decoded.
+    /// The kind of term follows from the dictionary section and the first byte without parsing the whole string:
+    /// predicates are always IRIs and only objects can be literals.
+    /// Subjects and objects come from the term cache of the dictionary, if enabled.
+    fn id_term(&self, id: Id, kind: &'static IdKind) -> Option<SimpleTerm<'_>> {
+        if *kind == IdKind::Predicate {
+            return self.predicate_strings().get(id - 1)?.as_deref().map(predicate_term);
+        }
+        string_term(self.hdt.dict.id_to_string_cached(id, kind).map_err(|e| error!("{e}")).ok()?, kind)
     }
 
-    fn id_term(&self, id: Id, kind: &'static IdKind) -> SimpleTerm<'static> {
-        IriRef::new_unchecked(MownStr::from(self.hdt.dict.id_to_string(id, kind).unwrap())).into_term()
+    // ID of the constant term of the matcher with the term itself, the ID is 0 if the term is not in the dictionary
+    fn constant_id<M: TermMatcher>(&self, m: &M, kind: &'static IdKind) -> Option<(Id, SimpleTerm<'static>)> {
+        m.constant().map(|t| {
+            let simple = SimpleTerm::from_term(t.as_simple());
+            (self.hdt.dict.string_to_id(&term_string(&simple), kind), simple)
+        })
+    }
+
+    // terms of all subjects or objects, each dictionary section is decoded in a single pass
+    fn section_terms(&self, kind: &'static IdKind) -> GTermSource<Self> {
+        Box::new(self.hdt.dict.iter(kind).filter_map(move |(_, s)| string_term(s, kind)).map(Ok))
+    }
+}
+
+fn predicate_term(p: &str) -> SimpleTerm<'_> {
+    SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from_str(p)))
+}
+
+/// Term of a decoded subject or object string, see [`HdtGraph::id_term`].
+fn string_term(mut s: String, kind: &IdKind) -> Option<SimpleTerm<'static>> {
+    match s.as_bytes() {
+        [b'"', ..] if *kind == IdKind::Object => literal_term(s),
+        [b'_', b':', ..] => {
+            s.drain(..2);
+            Some(SimpleTerm::BlankNode(BnodeId::new_unchecked(MownStr::from(s))))
+        }
+        _ => Some(SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(s)))),
     }
 }
 
 /// Create the correct Sophia term for a given resource string.
 /// Slow, use the appropriate method if you know which type (Literal, URI, or blank node) the string has.
-fn auto_term(s: MownStr) -> io::Result<SimpleTerm> {
+pub(crate) fn auto_term(s: MownStr) -> io::Result<SimpleTerm> {
     match s.chars().next() {
         None => Err(Error::new(ErrorKind::InvalidData, "empty input")),
         Some('"') => match s.rfind('"') {
@@ -76,10 +131,36 @@ fn auto_term(s: MownStr) -> io::Result<SimpleTerm> {
     }
 }
 
+/// Literal from a decoded object string that starts with a quotation mark, whose buffer is reused for the lexical form.
+/// Only the part after the last quotation mark is examined, because neither language tags nor datatype IRIs contain one.
+fn literal_term(mut s: String) -> Option<SimpleTerm<'static>> {
+    let Some(end) = s.rfind('"').filter(|&end| end > 0) else {
+        error!("missing right quotation mark in literal string {s}");
+        return None;
+    };
+    let mut suffix = s.split_off(end);
+    s.remove(0);
+    let lex = MownStr::from(s);
+    if suffix.len() == 1 {
+        return Some(SimpleTerm::LiteralDatatype(lex, IriRef::new_unchecked(MownStr::from_str(XSD_STRING))));
+    }
+    if suffix.starts_with("\"@") {
+        suffix.drain(..2);
+        return Some(SimpleTerm::LiteralLanguage(lex, LanguageTag::new_unchecked(MownStr::from(suffix))));
+    }
+    if suffix.starts_with("\"^^<") && suffix.ends_with('>') {
+        suffix.pop();
+        suffix.drain(..4);
+        return Some(SimpleTerm::LiteralDatatype(lex, IriRef::new_unchecked(MownStr::from(suffix))));
+    }
+    error!("invalid literal suffix {suffix}");
+    None
+}
+
 // Convert a SimpleTerm into the HDT String format.
 // Sophia doesn't include the _: prefix for blank node strings but HDT expects it
 // not needed for property terms, as they can't be blank nodes
-fn term_string(t: &SimpleTerm) -> String {
+pub(crate) fn term_string(t: &SimpleTerm) -> String {
     match t {
         SimpleTerm::BlankNode(b) => "_:".to_owned() + b.as_str(),
         SimpleTerm::Iri(i) => i.as_str().to_owned(),
@@ -87,9 +168,8 @@ fn term_string(t: &SimpleTerm) -> String {
             format!("\"{l}\"@{}", lang.as_str())
         }
         SimpleTerm::LiteralDatatype(l, dt) => {
-            let xsd_string: &str = "http://www.w3.org/2001/XMLSchema#string";
             let dts = dt.as_str();
-            if dts == xsd_string {
+            if dts == XSD_STRING {
                 format!("\"{l}\"")
             } else {
                 format!("\"{l}\"^^<{dts}>")
@@ -101,121 +181,214 @@ fn term_string(t: &SimpleTerm) -> String {
     }
 }
 
+/// Triple in the HDT string format as Sophia terms.
+pub(crate) fn sophia_triple((s, p, o): StringTriple) -> [SimpleTerm; 3] {
+    [auto_term(s).unwrap(), SimpleTerm::Iri(IriRef::new_unchecked(p)), auto_term(o).unwrap()]
+}
+
+/// Constant matchers as a triple pattern in the HDT string format, where `None` stands for a variable.
+pub(crate) fn string_pattern<S: TermMatcher, P: TermMatcher, O: TermMatcher>(
+    sm: &S, pm: &P, om: &O,
+) -> [Option<String>; 3] {
+    [
+        sm.constant().map(|s| term_string(&s.as_simple())),
+        pm.constant().map(|p| term_string(&p.as_simple())),
+        om.constant().map(|o| term_string(&o.as_simple())),
+    ]
+}
+
+/// Converts the results of querying the pattern from [`string_pattern`] into Sophia triples,
+/// keeping only those that also fit the non-constant matchers.
+pub(crate) fn filter_matching<'s, S, P, O>(
+    triples: impl Iterator<Item = StringTriple<'s>> + 's, pattern: &[Option<String>; 3], sm: S, pm: P, om: O,
+) -> impl Iterator<Item = Result<[SimpleTerm<'s>; 3], Infallible>> + 's
+where
+    S: TermMatcher + 's,
+    P: TermMatcher + 's,
+    O: TermMatcher + 's,
+{
+    let variable = pattern.each_ref().map(Option::is_none);
+    triples
+        .map(sophia_triple)
+        .filter(move |[s, p, o]| {
+            (!variable[0] || sm.matches(s)) && (!variable[1] || pm.matches(p)) && (!variable[2] || om.matches(o))
+        })
+        .map(Ok)
+}
+
 impl Graph for HdtGraph {
     type Triple<'a> = [SimpleTerm<'a>; 3];
     type Error = Infallible; // infallible for now, figure out what to put here later
 
     fn triples(&self) -> GTripleSource<Self> {
         debug!("Iterating through ALL triples in the HDT Graph. This can be inefficient for large graphs.");
-        Box::new(self.hdt.triples().map(move |(s, p, o)| {
-            Ok([auto_term(s).unwrap(), SimpleTerm::Iri(IriRef::new_unchecked(p)), auto_term(o).unwrap()])
-        }))
+        self.triples_matching(Any, Any, Any)
     }
 
-    /// Only supports constant and "any" matchers.
-    /// Non-constant matchers are supposed to be "any" matchers.
+    /// Constant matchers are looked up in the dictionary. Other matchers are checked on each decoded term
+    /// before the rest of the triple is decoded, where the last term of each position is reused for consecutive triples.
+    /// If the subject and object are variable, the predicate matcher is checked against the whole predicate section
+    /// and the triples are queried separately for each matching predicate.
     fn triples_matching<'s, S, P, O>(&'s self, sm: S, pm: P, om: O) -> GTripleSource<'s, Self>
     where
         S: TermMatcher + 's,
         P: TermMatcher + 's,
         O: TermMatcher + 's,
     {
-        let xso = sm.constant().map(|s| {
-            let simple = SimpleTerm::from_term(s.as_simple());
-            let id = self.hdt.dict.string_to_id(&term_string(&simple), &IdKind::Subject);
-            (simple, id)
-        });
-        let xpo = pm.constant().map(|p| {
-            let simple = SimpleTerm::from_term(p.as_simple());
-            let id = self.hdt.dict.string_to_id(&term_string(&simple), &IdKind::Predicate);
-            (simple, id)
-        });
-        let xoo = om.constant().map(|o| {
-            let simple = SimpleTerm::from_term(o.as_simple());
-            let id = self.hdt.dict.string_to_id(&term_string(&simple), &IdKind::Object);
-            (simple, id)
-        });
-        if [&xso, &xpo, &xoo].into_iter().flatten().any(|x| x.1 == 0) {
+        let xso = self.constant_id(&sm, &IdKind::Subject);
+        let xpo = self.constant_id(&pm, &IdKind::Predicate);
+        let xoo = self.constant_id(&om, &IdKind::Object);
+        if [&xso, &xpo, &xoo].into_iter().flatten().any(|x| x.0 == 0) {
             // at least one term does not exist in the graph
             return Box::new(iter::empty());
         }
-        // TODO: improve error handling
-        match (xso, xpo, xoo) {
-            (Some(s), Some(p), Some(o)) => {
-                if SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, p.1, o.1)).next().is_some() {
-                    Box::new(iter::once(Ok([s.0, p.0, o.0])))
-                } else {
-                    Box::new(iter::empty())
-                }
-            }
-            (Some(s), Some(p), None) => {
-                Box::new(SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, p.1, 0)).map(move |t| {
-                    Ok([
-                        s.0.clone(),
-                        p.0.clone(),
-                        auto_term(self.hdt.dict.id_to_string(t.object_id, &IdKind::Object).unwrap().into())
-                            .unwrap()
-                            .into_term(),
-                    ])
-                }))
-            }
-            (Some(s), None, Some(o)) => {
-                Box::new(SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, 0, o.1)).map(move |t| {
-                    Ok([s.0.clone(), self.id_term(t.predicate_id, &IdKind::Predicate), o.0.clone()])
-                }))
-            }
-            (Some(s), None, None) => {
-                Box::new(SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, 0, 0)).map(move |t| {
-                    Ok([
-                        s.0.clone(),
-                        self.id_term(t.predicate_id, &IdKind::Predicate),
-                        auto_term(MownStr::from(
-                            self.hdt.dict.id_to_string(t.object_id, &IdKind::Object).unwrap(),
-                        ))
-                        .expect("auto term failed with object")
-                        .into_term(),
-                    ])
-                }))
+        let [s, p, o] = [&xso, &xpo, &xoo].map(|x| x.as_ref().map_or(0, |x| x.0));
+        let triples = &self.hdt.triples;
+        // the predicate section is small compared to the triples
+        let predicates: Option<Vec<Id>> = (s == 0 && p == 0 && o == 0).then(|| {
+            (1..=self.hdt.dict.predicates.num_strings())
+                .filter(|&id| self.id_term(id, &IdKind::Predicate).is_some_and(|p| pm.matches(&p)))
+                .collect()
+        });
+        let filter_predicates = predicates.is_none();
+        let ids: Box<dyn Iterator<Item = TripleId> + Send + '_> = match predicates {
+            Some(ids) if ids.len() < self.hdt.dict.predicates.num_strings() => {
+                debug!("querying {} matching predicates separately", ids.len());
+                Box::new(ids.into_iter().flat_map(move |p| triples.triples_with_pattern(&TripleId::new(0, p, 0))))
             }
-            (None, Some(p), Some(o)) => Box::new(
-                PredicateObjectIter::new(&self.hdt.triples, p.1, o.1)
-                    .map(move |sid| Ok([self.id_term(sid, &IdKind::Subject), p.0.clone(), o.0.clone()])),
-            ),
-            (None, Some(p), None) => Box::new(PredicateIter::new(&self.hdt.triples, p.1).map(move |t| {
-                Ok([
-                    self.id_term(t.subject_id, &IdKind::Subject),
-                    p.0.clone(),
-                    self.id_term(t.object_id, &IdKind::Object),
-                ])
-            })),
-            (None, None, Some(o)) => Box::new(ObjectIter::new(&self.hdt.triples, o.1).map(move |t| {
-                Ok([
-                    auto_term(MownStr::from(self.hdt.dict.id_to_string(t.subject_id, &IdKind::Subject).unwrap()))
-                        .unwrap(),
-                    self.id_term(t.predicate_id, &IdKind::Predicate),
-                    o.0.clone(),
-                ])
-            })),
-            (None, None, None) => self.triples(),
+            _ => triples.triples_with_pattern(&TripleId::new(s, p, o)),
+        };
+        let mut sp = Position::new(self, &IdKind::Subject, sm, xso, true);
+        let mut pp = Position::new(self, &IdKind::Predicate, pm, xpo, filter_predicates);
+        let mut op = Position::new(self, &IdKind::Object, om, xoo, true);
+        let mut ids = ids.peekable();
+        Box::new(iter::from_fn(move || loop {
+            let t = ids.next()?;
+            let next = ids.peek().copied();
+            let Some(s) = sp.term(t.subject_id, next.map(|n| n.subject_id)) else { continue };
+            let Some(o) = op.term(t.object_id, next.map(|n| n.object_id)) else { continue };
+            let Some(p) = pp.term(t.predicate_id, next.map(|n| n.predicate_id)) else { continue };
+            return Some(Ok([s, p, o]));
+        }))
+    }
+
+    /// Iterates over the shared and subject sections of the dictionary.
+    fn subjects(&self) -> GTermSource<Self> {
+        self.section_terms(&IdKind::Subject)
+    }
+
+    /// Iterates over the predicate section of the dictionary.
+    fn predicates(&self) -> GTermSource<Self> {
+        Box::new(self.predicate_strings().iter().flatten().map(|p| Ok(predicate_term(p))))
+    }
+
+    /// Iterates over the shared and object sections of the dictionary.
+    fn objects(&self) -> GTermSource<Self> {
+        self.section_terms(&IdKind::Object)
+    }
+
+    /// Iterates over the IRIs of the subject and object sections of the dictionary,
+    /// followed by the predicates that are neither subjects nor objects.
+    fn iris(&self) -> GTermSource<Self> {
+        let dict = &self.hdt.dict;
+        let resources = [&dict.shared, &dict.subjects, &dict.objects]
+            .into_iter()
+            .flat_map(DictSectPFC::iter)
+            .filter(|s| !s.starts_with('"') && !s.starts_with("_:"))
+            .map(|s| Ok(SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(s)))));
+        let predicates = self
+            .predicate_strings()
+            .iter()
+            .flatten()
+            .filter(|p| dict.string_to_id(p, &IdKind::Subject) == 0 && dict.string_to_id(p, &IdKind::Object) == 0)
+            .map(|p| Ok(predicate_term(p)));
+        Box::new(resources.chain(predicates))
+    }
+
+    /// Iterates over the blank nodes of the subject and object sections of the dictionary.
+    fn blank_nodes(&self) -> GTermSource<Self> {
+        let dict = &self.hdt.dict;
+        Box::new(
+            [&dict.shared, &dict.subjects, &dict.objects]
+                .into_iter()
+                .flat_map(DictSectPFC::iter)
+                .filter(|s| s.starts_with("_:"))
+                .filter_map(|s| string_term(s, &IdKind::Subject))
+                .map(Ok),
+        )
+    }
+
+    /// Iterates over the start of the object section of the dictionary,
+    /// where the literals are because their quotation mark sorts before IRIs and blank nodes.
+    fn literals(&self) -> GTermSource<Self> {
+        Box::new(self.hdt.dict.objects.iter().take_while(|s| s.starts_with('"')).filter_map(literal_term).map(Ok))
+    }
+
+    /// Looks up the terms in the dictionary and the triple in the bitmap triples in O(log n).
+    fn contains<TS, TP, TO>(&self, s: TS, p: TP, o: TO) -> GResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let dict = &self.hdt.dict;
+        let t = TripleId::new(
+            dict.string_to_id(&term_string(&s.as_simple()), &IdKind::Subject),
+            dict.string_to_id(&term_string(&p.as_simple()), &IdKind::Predicate),
+            dict.string_to_id(&term_string(&o.as_simple()), &IdKind::Object),
+        );
+        Ok(t.subject_id != 0 && t.predicate_id != 0 && t.object_id != 0 && self.hdt.triples.position(&t).is_some())
+    }
+}
+
+/// Decodes the terms of one position of the triples and checks them against the matcher.
+/// The last term is kept while its ID repeats in the next triple, as subjects and predicates do,
+/// so that the decoded string is cloned only for the repetitions and moved out on its last use.
+struct Position<'a, M> {
+    graph: &'a HdtGraph,
+    kind: &'static IdKind,
+    matcher: M,
+    // whether the decoded terms still need to be checked against the matcher
+    filter: bool,
+    // last ID with its term if it repeats in the next triple, the term is `None` if it doesn't fit the matcher
+    last: Option<(Id, Option<SimpleTerm<'a>>)>,
+}
+
+impl<'a, M: TermMatcher> Position<'a, M> {
+    // a constant is never decoded because all IDs in its position are the same
+    fn new(
+        graph: &'a HdtGraph, kind: &'static IdKind, matcher: M, constant: Option<(Id, SimpleTerm<'static>)>,
+        filter: bool,
+    ) -> Self {
+        let last = constant.map(|(id, term)| (id, Some(term)));
+        Position { graph, kind, matcher, filter, last }
+    }
+
+    /// The term with the given ID if it fits the matcher, where `next` is the ID in this position of the next triple.
+    fn term(&mut self, id: Id, next: Option<Id>) -> Option<SimpleTerm<'a>> {
+        let term = match self.last.take() {
+            Some((last, term)) if last == id => term,
+            _ => self.graph.id_term(id, self.kind).filter(|t| !self.filter || self.matcher.matches(t)),
+        };
+        if next == Some(id) {
+            self.last = Some((id, term.clone()));
         }
+        term
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::init;
+    use crate::tests::{hdt, init, load_snikmeta};
     use sophia::api::prelude::Triple;
-    use sophia::api::term::matcher::Any;
-    use std::fs::File;
+    use std::collections::BTreeSet;
     use std::result::Result;
 
     #[test]
     fn test_graph() {
         init();
-        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
-        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
-        let graph = HdtGraph::new(hdt);
+        let graph = HdtGraph::new(load_snikmeta());
         let triples: Vec<Result<[SimpleTerm<'_>; 3], Infallible>> = graph.triples().collect();
         assert_eq!(triples.len(), 327);
         let meta_top = "http://www.snik.eu/ontology/meta/Top";
@@ -279,4 +452,137 @@ mod tests {
         assert!(graph.triples_matching(testo).count() == 1);
         */
     }
+
+    #[test]
+    fn matchers() {
+        init();
+        let graph = HdtGraph::new(load_snikmeta());
+        let iri = |s: &'static str| SimpleTerm::Iri(IriRef::new_unchecked(s.into()));
+        let top = iri("http://www.snik.eu/ontology/meta/Top");
+        let label = iri("http://www.w3.org/2000/01/rdf-schema#label");
+        let type_ = iri("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
+        let missing = iri("http://example.org/doesnotexist");
+        let lang = SimpleTerm::LiteralLanguage("top class".into(), LanguageTag::new_unchecked("en".into()));
+        let all: Vec<_> = graph.triples().map(Result::unwrap).collect();
+        let count = |f: &dyn Fn(&[SimpleTerm; 3]) -> bool| all.iter().filter(|t| f(t)).count();
+
+        // finite sets of predicates
+        let preds = [label.clone(), type_.clone()];
+        assert_eq!(count(&|t| preds.contains(&t[1])), graph.triples_matching(Any, preds.clone(), Any).count());
+        assert_eq!(0, graph.triples_matching(Any, [missing.clone(), missing.clone()], Any).count());
+        assert_eq!(
+            count(&|t| t[0] == top && preds.contains(&t[1])),
+            graph.triples_matching([top.clone()], preds.clone(), Any).count()
+        );
+        // finite sets of subjects and objects are checked on each triple
+        let subjects = [top.clone(), missing.clone()];
+        assert_eq!(count(&|t| t[0] == top), graph.triples_matching(subjects.clone(), Any, Any).count());
+        let objects = [lang.clone(), missing];
+        assert_eq!(
+            vec![[top.clone(), label.clone(), lang.clone()]],
+            graph.triples_matching(Any, Any, objects.clone()).map(Result::unwrap).collect::<Vec<_>>()
+        );
+        assert_eq!(1, graph.triples_matching(subjects, preds, objects).count());
+        // constant and non-constant matchers together
+        assert_eq!(1, graph.triples_matching(Some(&top), [label, type_], Some(&lang)).count());
+    }
+
+    // every pattern with the terms of the given triple as constants returns the triple
+    fn check_patterns(graph: &HdtGraph, t: &[SimpleTerm; 3]) {
+        let [s, p, o] = t;
+        let results: [Vec<_>; 8] = [
+            graph.triples_matching(Some(s), Some(p), Some(o)).map(Result::unwrap).collect(),
+            graph.triples_matching(Some(s), Some(p), Any).map(Result::unwrap).collect(),
+            graph.triples_matching(Some(s), Any, Some(o)).map(Result::unwrap).collect(),
+            graph.triples_matching(Some(s), Any, Any).map(Result::unwrap).collect(),
+            graph.triples_matching(Any, Some(p), Some(o)).map(Result::unwrap).collect(),
+            graph.triples_matching(Any, Some(p), Any).map(Result::unwrap).collect(),
+            graph.triples_matching(Any, Any, Some(o)).map(Result::unwrap).collect(),
+            graph.triples_matching(Any, Any, Any).map(Result::unwrap).collect(),
+        ];
+        for (i, result) in results.iter().enumerate() {
+            assert!(result.contains(t), "pattern {i} misses {t:?} in {result:?}");
+        }
+    }
+
+    #[test]
+    fn term_kinds() {
+        init();
+        let (b, x) = ("_:b1", "http://example.org/x");
+        let (p, q) = ("http://example.org/p", "http://example.org/q");
+        let typed = "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>";
+        let input =
+            [[b, p, typed], [b, q, "\"hallo \"welt\"\"@de"], [x, p, b], [x, q, "\"plain\""], [x, q, "_:b2"]];
+        let graph = HdtGraph::new(hdt(&input));
+        assert_eq!(input.len(), graph.triples().count());
+        for [s, p, o] in input {
+            // auto_term parses the whole string
+            let t = sophia_triple((s.into(), p.into(), o.into()));
+            check_patterns(&graph, &t);
+        }
+        let with_p: Vec<_> = graph
+            .triples_matching(Any, Some(&SimpleTerm::Iri(IriRef::new_unchecked(p.into()))), Any)
+            .map(Result::unwrap)
+            .collect();
+        assert!(with_p.iter().any(|t| matches!(t[0], SimpleTerm::BlankNode(_))));
+        assert!(with_p.iter().any(|t| matches!(t[2], SimpleTerm::BlankNode(_))));
+        assert!(with_p.iter().any(|t| matches!(t[2], SimpleTerm::LiteralDatatype(..))));
+    }
+
+    // the native implementations of the term methods agree with the positions of the triples
+    fn check_terms(graph: &HdtGraph) {
+        let triples: Vec<_> = graph.triples().map(Result::unwrap).collect();
+        let distinct = |filter: fn(&SimpleTerm) -> bool, positions: &[usize]| -> Vec<String> {
+            let set: BTreeSet<String> = triples
+                .iter()
+                .flat_map(|t| positions.iter().map(move |&i| &t[i]))
+                .filter(|t| filter(t))
+                .map(term_string)
+                .collect();
+            set.into_iter().collect()
+        };
+        let sorted = |terms: GTermSource<HdtGraph>| -> Vec<String> {
+            let mut v: Vec<_> = terms.map(|t| term_string(&t.unwrap())).collect();
+            v.sort();
+            v
+        };
+        assert_eq!(distinct(|_| true, &[0]), sorted(graph.subjects()));
+        assert_eq!(distinct(|_| true, &[1]), sorted(graph.predicates()));
+        assert_eq!(distinct(|_| true, &[2]), sorted(graph.objects()));
+        assert_eq!(distinct(|t| matches!(t, SimpleTerm::Iri(_)), &[0, 1, 2]), sorted(graph.iris()));
+        assert_eq!(distinct(|t| matches!(t, SimpleTerm::BlankNode(_)), &[0, 2]), sorted(graph.blank_nodes()));
+        let is_literal =
+            |t: &SimpleTerm| matches!(t, SimpleTerm::LiteralDatatype(..) | SimpleTerm::LiteralLanguage(..));
+        assert_eq!(distinct(is_literal, &[2]), sorted(graph.literals()));
+        for [s, p, o] in &triples {
+            assert!(graph.contains(s, p, o).unwrap());
+        }
+    }
+
+    #[test]
+    fn terms() {
+        init();
+        let graph = HdtGraph::new(load_snikmeta());
+        check_terms(&graph);
+        let (b, x, p) = ("_:b1", "http://example.org/x", "http://example.org/p");
+        // p is also a subject, so it is only returned once by iris()
+        let graph = HdtGraph::new(hdt(&[[b, p, "\"1\"^^<http://example.org/dt>"], [x, p, b], [p, p, "\"p\"@en"]]));
+        check_terms(&graph);
+        assert_eq!(2, graph.iris().count());
+        let [s, p] = [x, "http://example.org/none"].map(|i| SimpleTerm::Iri(IriRef::new_unchecked(i.into())));
+        assert!(!graph.contains(&s, &p, &s).unwrap());
+    }
+
+    #[test]
+    fn position() {
+        init();
+        let (x, y, p) = ("http://example.org/x", "http://example.org/y", "http://example.org/p");
+        let graph = HdtGraph::new(hdt(&[[x, p, y], [y, p, x]]));
+        let mut sp = Position::new(&graph, &IdKind::Subject, Any, None, true);
+        // the term is only kept while the ID repeats
+        for (id, next) in [(1, Some(1)), (1, Some(2)), (2, None), (1, None)] {
+            assert_eq!(graph.id_term(id, &IdKind::Subject), sp.term(id, next));
+            assert_eq!(next == Some(id), sp.last.is_some());
+        }
+    }
 }
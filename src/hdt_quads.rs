@@ -0,0 +1,308 @@
+//! Named graphs in HDTQ, the quad extension of HDT in hdt-java, which adds a graph section to the dictionary
+//! and a bitmap for each graph that marks the triples in it.
+//! The dictionary has the graph section after the four usual ones and the triples section is followed by the number of graphs
+//! and one bitmap per graph in the order of the graph section, each with one bit per triple in SPO order.
+//! The default graph is stored with the empty string as graph name.
+use crate::builder::hdt_from_triples;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
+use crate::containers::{Bitmap, ControlInfo, ControlType};
+use crate::dict_sect_pfc::DictSectPFCBuilder;
+use crate::hdt::Hdt;
+use crate::hdt_graph::{auto_term, sophia_triple, string_pattern, term_string};
+use crate::header::Header;
+use crate::triples::{Id, TripleId, TriplesBitmap};
+use crate::{DictSectPFC, FourSectDict, IdKind};
+use log::error;
+use sophia::api::dataset::{DQuadSource, Dataset};
+use sophia::api::quad::Spog;
+use sophia::api::term::matcher::{GraphNameMatcher, TermMatcher};
+use sophia::api::term::{SimpleTerm, Term};
+use sophia::api::MownStr;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Error, ErrorKind, Write};
+use std::iter;
+
+/// Dictionary format of HDTQ with a graph section.
+const DICT_FORMAT: &str = "<http://purl.org/HDT/hdt#dictionaryFourQuad>";
+/// Triples format of HDTQ with graph bitmaps.
+const TRIPLES_FORMAT: &str = "<http://purl.org/HDT/hdt#triplesBitmapQuad>";
+/// Graph name of the default graph.
+const DEFAULT_GRAPH: &str = "";
+
+type StringQuad<'a> = (MownStr<'a>, MownStr<'a>, MownStr<'a>, MownStr<'a>);
+
+/// In-memory representation of an RDF dataset loaded from an HDTQ file.
+/// Allows queries by quad patterns, where the graph names use the same format as the other terms
+/// and the empty string stands for the default graph.
+#[derive(Debug)]
+pub struct HdtQuads {
+    /// header, dictionary without graph names and the triples of all graphs without duplicates
+    pub hdt: Hdt,
+    /// graph names
+    pub graphs: DictSectPFC,
+    /// for each graph, which triples belong to it, indexed by graph ID - 1 and triple position in SPO order
+    pub graph_bitmaps: Vec<Bitmap>,
+}
+
+impl HdtQuads {
+    /// Creates an immutable instance from the given reader, which must point to the beginning of an HDTQ file.
+    /// Plain HDT files are rejected, use [`Hdt::new`] for those.
+    pub fn new<R: BufRead>(mut reader: R) -> io::Result<Self> {
+        ControlInfo::read(&mut reader)?;
+        let header = Header::read(&mut reader)?;
+        let dict_ci = ControlInfo::read(&mut reader)?;
+        if dict_ci.format != DICT_FORMAT {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("named graphs require the dictionary format {DICT_FORMAT} but found {}", dict_ci.format),
+            ));
+        }
+        let mut dict = FourSectDict::read_sects(&mut reader)?;
+        let mut graphs = DictSectPFC::read(&mut reader)?;
+        let triples_ci = ControlInfo::read(&mut reader)?;
+        if triples_ci.format != TRIPLES_FORMAT {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "named graphs require the triples format {TRIPLES_FORMAT} but found {}",
+                    triples_ci.format
+                ),
+            ));
+        }
+        let triples = TriplesBitmap::read(&mut reader, &triples_ci)?;
+        let (num_graphs, _) = read_vbyte(&mut reader)?;
+        if num_graphs != graphs.num_strings() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{num_graphs} graph bitmaps for {} graph names", graphs.num_strings()),
+            ));
+        }
+        let graph_bitmaps = (0..num_graphs).map(|_| Bitmap::read(&mut reader)).collect::<io::Result<Vec<_>>>()?;
+        dict.validate()?;
+        let crc_handle =
+            graphs.crc_handle.take().ok_or_else(|| Error::other("graph section CRC already checked"))?;
+        if !crc_handle.join().map_err(|_| Error::other("panic while checking the graph section CRC"))? {
+            return Err(Error::new(ErrorKind::InvalidData, "CRC Error in graph dictionary section."));
+        }
+        Ok(HdtQuads { hdt: Hdt { header, dict, triples }, graphs, graph_bitmaps })
+    }
+
+    /// Dataset with the given quads in the string format of the dictionary, where the graph name comes last
+    /// and is empty for the default graph.
+    /// All terms are kept in memory, so this is only suitable for small datasets.
+    pub fn from_quads(quads: &[[&str; 4]]) -> Self {
+        let triples: Vec<[&str; 3]> = quads.iter().map(|[s, p, o, _]| [*s, *p, *o]).collect();
+        let hdt = hdt_from_triples(&triples);
+        let mut builder = DictSectPFCBuilder::new(DictSectPFCBuilder::DEFAULT_BLOCK_SIZE);
+        for g in quads.iter().map(|q| q[3]).collect::<BTreeSet<_>>() {
+            builder.push(g.as_bytes());
+        }
+        let graphs = builder.build();
+        let mut words = vec![vec![0u64; hdt.triples.adjlist_z.len().div_ceil(64)]; graphs.num_strings()];
+        for [s, p, o, g] in quads {
+            let dict = &hdt.dict;
+            let t = TripleId::new(
+                dict.string_to_id(s, &IdKind::Subject),
+                dict.string_to_id(p, &IdKind::Predicate),
+                dict.string_to_id(o, &IdKind::Object),
+            );
+            let pos = hdt.triples.position(&t).expect("triple of a quad is missing");
+            words[graphs.string_to_id(g) - 1][pos / 64] |= 1 << (pos % 64);
+        }
+        HdtQuads { hdt, graphs, graph_bitmaps: words.into_iter().map(Bitmap::new).collect() }
+    }
+
+    /// Writes the dataset as HDTQ in the format read by [`Self::new`].
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        ControlInfo::new(ControlType::Global, "<http://purl.org/HDT/hdt#HDTv1>").write(w)?;
+        self.hdt.header.write(w)?;
+        self.hdt.dict.write_sects(w, DICT_FORMAT, &[&self.graphs])?;
+        self.hdt.triples.write_format(w, TRIPLES_FORMAT)?;
+        w.write_all(&encode_vbyte(self.graph_bitmaps.len()))?;
+        for bitmap in &self.graph_bitmaps {
+            bitmap.write(w, self.hdt.triples.adjlist_z.len())?;
+        }
+        Ok(())
+    }
+
+    /// Recursive size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.hdt.size_in_bytes()
+            + self.graphs.size_in_bytes()
+            + self.graph_bitmaps.iter().map(Bitmap::size_in_bytes).sum::<usize>()
+    }
+
+    /// Names of all graphs in ascending order.
+    pub fn graph_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.graphs.iter()
+    }
+
+    /// Get all quads that fit the given quad pattern, where `None` stands for a variable.
+    /// Without a triple pattern, the triples of the graphs in question are found by the set bits of their bitmaps.
+    /// Otherwise each triple that fits the triple pattern is checked against the bitmaps of the graphs in question.
+    pub fn quads_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, gp: Option<&str>,
+    ) -> impl Iterator<Item = StringQuad<'static>> + '_ {
+        let graph_ids = match gp.map(|g| self.graphs.string_to_id(g)) {
+            None => 1..=self.graphs.num_strings(),
+            Some(0) => 1..=0,
+            Some(id) => id..=id,
+        };
+        let quads: Box<dyn Iterator<Item = (TripleId, Id)> + '_> = if graph_ids.is_empty() {
+            Box::new(iter::empty())
+        } else if sp.is_none() && pp.is_none() && op.is_none() {
+            Box::new(graph_ids.flat_map(move |g| self.graph_triples(g).map(move |t| (t, g))))
+        } else {
+            Box::new(
+                self.hdt
+                    .triple_ids_with_pattern(sp, pp, op)
+                    .filter_map(move |t| self.hdt.triples.position(&t).map(|pos| (t, pos)))
+                    .flat_map(move |(t, pos)| {
+                        graph_ids.clone().filter(move |&g| self.in_graph(g, pos)).map(move |g| (t, g))
+                    }),
+            )
+        };
+        quads.filter_map(move |(t, g)| self.translate(t, g))
+    }
+
+    // triples of the graph with the given ID in SPO order
+    fn graph_triples(&self, g: Id) -> impl Iterator<Item = TripleId> + '_ {
+        let bits = &self.graph_bitmaps[g - 1].dict;
+        (0..).map_while(move |k| bits.select1(k)).map(move |pos| self.hdt.triples.triple_at(pos as usize))
+    }
+
+    // whether the triple at the given position is in the graph with the given ID
+    fn in_graph(&self, g: Id, pos: usize) -> bool {
+        let bits = &self.graph_bitmaps[g - 1].dict;
+        (pos as u64) < bits.len() as u64 && bits.get_bit(pos as u64)
+    }
+
+    fn translate(&self, t: TripleId, g: Id) -> Option<StringQuad<'static>> {
+        let (s, p, o) = self.hdt.translate_id(t).map_err(|e| error!("{e}")).ok()?;
+        let g = self.graphs.extract(g).map_err(|e| error!("Cannot translate graph ID {g}: {e}")).ok()?;
+        Some((s, p, o, MownStr::from(g)))
+    }
+}
+
+// quad in the format of the HDT as Sophia terms, fails if the graph name is neither an IRI nor a blank node
+fn sophia_quad((s, p, o, g): StringQuad) -> io::Result<Spog<SimpleTerm>> {
+    if &*g == DEFAULT_GRAPH {
+        return Ok((sophia_triple((s, p, o)), None));
+    }
+    match auto_term(g)? {
+        g @ (SimpleTerm::Iri(_) | SimpleTerm::BlankNode(_)) => Ok((sophia_triple((s, p, o)), Some(g))),
+        g => Err(Error::new(ErrorKind::InvalidData, format!("invalid graph name {}", term_string(&g)))),
+    }
+}
+
+impl Dataset for HdtQuads {
+    type Quad<'a> = Spog<SimpleTerm<'a>>;
+    type Error = io::Error;
+
+    fn quads(&self) -> DQuadSource<Self> {
+        Box::new(self.quads_with_pattern(None, None, None, None).map(sophia_quad))
+    }
+
+    /// Constant matchers are used as a quad pattern, other matchers filter the results.
+    fn quads_matching<'s, S, P, O, G>(&'s self, sm: S, pm: P, om: O, gm: G) -> DQuadSource<'s, Self>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+        G: GraphNameMatcher + 's,
+    {
+        let pattern = string_pattern(&sm, &pm, &om);
+        let [sp, pp, op] = pattern.each_ref().map(Option::as_deref);
+        let gp = match gm.constant() {
+            Some(None) => Some(DEFAULT_GRAPH.to_owned()),
+            Some(Some(g)) => Some(term_string(&g.as_simple())),
+            None => None,
+        };
+        let variable = [sp.is_none(), pp.is_none(), op.is_none(), gp.is_none()];
+        Box::new(self.quads_with_pattern(sp, pp, op, gp.as_deref()).map(sophia_quad).filter(move |q| match q {
+            Ok(([s, p, o], g)) => {
+                (!variable[0] || sm.matches(s))
+                    && (!variable[1] || pm.matches(p))
+                    && (!variable[2] || om.matches(o))
+                    && (!variable[3] || gm.matches(g.as_ref()))
+            }
+            Err(_) => true,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use sophia::api::term::matcher::Any;
+    use sophia::api::term::IriRef;
+    use std::fs::File;
+
+    fn strings(quads: impl Iterator<Item = StringQuad<'static>>) -> Vec<[String; 4]> {
+        let mut v: Vec<_> =
+            quads.map(|(s, p, o, g)| [s.to_string(), p.to_string(), o.to_string(), g.to_string()]).collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn quads() {
+        init();
+        let (x, y, z) = ("http://example.org/x", "http://example.org/y", "http://example.org/z");
+        let (p, q) = ("http://example.org/p", "http://example.org/q");
+        let (g1, g2) = ("http://example.org/g1", "http://example.org/g2");
+        let input = [[x, p, y, g1], [x, p, y, g2], [y, q, "\"literal\"@en", g1], [z, q, x, g2], [z, q, x, g2]];
+        let quads = HdtQuads::from_quads(&input);
+        let mut expected: Vec<[String; 4]> = input[..4].iter().map(|t| t.map(str::to_owned)).collect();
+        expected.sort();
+        assert_eq!(3, quads.hdt.triples().count());
+        assert_eq!(vec![g1, g2], quads.graph_names().collect::<Vec<_>>());
+        assert_eq!(expected, strings(quads.quads_with_pattern(None, None, None, None)));
+        assert_eq!(2, quads.quads_with_pattern(None, None, None, Some(g1)).count());
+        assert_eq!(2, quads.quads_with_pattern(Some(x), Some(p), Some(y), None).count());
+        assert_eq!(
+            vec![[z, q, x, g2].map(str::to_owned)],
+            strings(quads.quads_with_pattern(None, Some(q), None, Some(g2)))
+        );
+        assert_eq!(0, quads.quads_with_pattern(Some(z), None, None, Some(g1)).count());
+        assert_eq!(0, quads.quads_with_pattern(None, None, None, Some("http://example.org/none")).count());
+
+        // written and read back
+        let mut written = Vec::new();
+        quads.write(&mut written).unwrap();
+        let read = HdtQuads::new(written.as_slice()).unwrap();
+        assert_eq!(expected, strings(read.quads_with_pattern(None, None, None, None)));
+        assert!(Hdt::from_bytes(&written).is_err());
+
+        // Sophia
+        let g = SimpleTerm::Iri(IriRef::new_unchecked(g2.into()));
+        assert_eq!(4, read.quads().count());
+        assert_eq!(2, read.quads_matching(Any, Any, Any, Some(Some(&g))).count());
+        assert_eq!(0, read.quads_matching(Any, Any, Any, Some(None::<&SimpleTerm>)).count());
+
+        // the default graph has an empty name
+        let with_default = HdtQuads::from_quads(&[[x, p, y, DEFAULT_GRAPH], [z, q, x, g1]]);
+        let mut written = Vec::new();
+        with_default.write(&mut written).unwrap();
+        let read = HdtQuads::new(written.as_slice()).unwrap();
+        assert_eq!(1, read.quads_with_pattern(None, None, None, Some(DEFAULT_GRAPH)).count());
+        let default: Vec<_> =
+            read.quads_matching(Any, Any, Any, Some(None::<&SimpleTerm>)).map(Result::unwrap).collect();
+        assert_eq!(1, default.len());
+        assert_eq!(None, default[0].1);
+
+        // graph names that are neither IRIs nor blank nodes are errors
+        let invalid = HdtQuads::from_quads(&[[x, p, y, "\"literal\""]]);
+        assert_eq!(ErrorKind::InvalidData, invalid.quads().next().unwrap().unwrap_err().kind());
+    }
+
+    #[test]
+    fn plain_hdt() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let err = HdtQuads::new(std::io::BufReader::new(file)).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+}
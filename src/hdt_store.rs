@@ -0,0 +1,249 @@
+//! Mutable graph that keeps changes to an immutable HDT in memory.
+use crate::builder::{build, hdt_from_triples};
+use crate::hdt::{Hdt, StringTriple};
+use crate::hdt_graph::{filter_matching, sophia_triple, string_pattern, term_string};
+use crate::triples::TripleId;
+use log::error;
+use sophia::api::graph::{GTripleSource, Graph, MgResult, MutableGraph};
+use sophia::api::term::{matcher::TermMatcher, SimpleTerm, Term};
+use sophia::api::MownStr;
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::io::{self, Write};
+
+/// RDF graph that can be modified, consisting of a read-only HDT and the changes to it in memory.
+/// Intended for editing small parts of a large graph, because the inserted triples are stored as strings
+/// and pattern queries scan all of them.
+/// Terms use the same format as in [`Hdt::triples_with_pattern`].
+/// # Examples
+/// ```no_run
+/// # let file = std::fs::File::open("example.hdt").expect("error opening file");
+/// let hdt = hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT");
+/// let mut store = hdt::HdtStore::new(hdt);
+/// store.insert("http://example.org/s", "http://example.org/p", "\"new\"@en");
+/// store.remove("http://example.org/s", "http://example.org/p", "\"old\"@en");
+/// let mut out = std::fs::File::create("edited.hdt").expect("error creating file");
+/// store.write(&mut out).expect("error writing HDT");
+/// ```
+#[derive(Debug)]
+pub struct HdtStore {
+    hdt: Hdt,
+    // triples that are not in the HDT
+    inserted: BTreeSet<[String; 3]>,
+    // IDs of the triples of the HDT that are removed
+    removed: BTreeSet<TripleId>,
+}
+
+impl HdtStore {
+    /// Wrap the given HDT without any changes.
+    pub const fn new(hdt: Hdt) -> Self {
+        HdtStore { hdt, inserted: BTreeSet::new(), removed: BTreeSet::new() }
+    }
+
+    /// The HDT without the changes that are not compacted yet.
+    pub const fn hdt(&self) -> &Hdt {
+        &self.hdt
+    }
+
+    /// Whether there are changes that are not part of the HDT yet.
+    pub const fn is_modified(&self) -> bool {
+        !self.inserted.is_empty() || !self.removed.is_empty()
+    }
+
+    /// Number of triples including the changes.
+    pub fn len(&self) -> usize {
+        self.hdt.pattern_count(None, None, None) - self.removed.len() + self.inserted.len()
+    }
+
+    /// Whether there are no triples including the changes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // ID of the triple in the HDT, whether it is removed or not
+    fn hdt_id(&self, s: &str, p: &str, o: &str) -> Option<TripleId> {
+        self.hdt.triple_ids_with_pattern(Some(s), Some(p), Some(o)).next()
+    }
+
+    /// Whether the graph contains the given triple including the changes.
+    pub fn contains(&self, s: &str, p: &str, o: &str) -> bool {
+        match self.hdt_id(s, p, o) {
+            Some(id) => !self.removed.contains(&id),
+            None => self.inserted.contains(&[s, p, o].map(str::to_owned)),
+        }
+    }
+
+    /// Add the given triple, returns `false` if the graph already contained it.
+    pub fn insert(&mut self, s: &str, p: &str, o: &str) -> bool {
+        match self.hdt_id(s, p, o) {
+            Some(id) => self.removed.remove(&id),
+            None => self.inserted.insert([s, p, o].map(str::to_owned)),
+        }
+    }
+
+    /// Remove the given triple, returns `false` if the graph didn't contain it.
+    pub fn remove(&mut self, s: &str, p: &str, o: &str) -> bool {
+        match self.hdt_id(s, p, o) {
+            Some(id) => self.removed.insert(id),
+            None => self.inserted.remove(&[s, p, o].map(str::to_owned)),
+        }
+    }
+
+    /// Get all triples that fit the given triple pattern including the changes, where `None` stands for a variable.
+    /// The remaining triples of the HDT come first, followed by the inserted ones.
+    pub fn triples_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> impl Iterator<Item = StringTriple<'_>> + '_ {
+        let hdt_triples = self
+            .hdt
+            .triple_ids_with_pattern(sp, pp, op)
+            .filter(move |t| !self.removed.contains(t))
+            .filter_map(move |t| self.hdt.translate_id(t).map_err(|e| error!("{e}")).ok());
+        let pattern = [sp, pp, op].map(|x| x.map(str::to_owned));
+        let inserted = self
+            .inserted
+            .iter()
+            .filter(move |t| pattern.iter().zip(t.iter()).all(|(x, term)| x.as_ref().is_none_or(|x| x == term)))
+            .map(|[s, p, o]| (MownStr::from_str(s), MownStr::from_str(p), MownStr::from_str(o)));
+        hdt_triples.chain(inserted)
+    }
+
+    /// Apply the changes by replacing the HDT with a new one, which is built in a single merge pass over the triple IDs
+    /// of the HDT without the removed triples and of a small HDT with the inserted ones, like [`cat`](crate::cat).
+    /// The header is carried over, so statistics in it such as the number of triples may be outdated.
+    pub fn compact(&mut self) {
+        if !self.is_modified() {
+            return;
+        }
+        let inserted: Vec<[&str; 3]> = self.inserted.iter().map(|t| t.each_ref().map(String::as_str)).collect();
+        let inserted = hdt_from_triples(&inserted);
+        let removed = &self.removed;
+        let mut hdt = build([&self.hdt, &inserted], [&|t| !removed.contains(t), &|_| true]);
+        hdt.header = std::mem::take(&mut self.hdt.header);
+        *self = HdtStore::new(hdt);
+    }
+
+    /// Apply the changes with [`Self::compact`] and write the resulting HDT with [`Hdt::write`].
+    pub fn write<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.compact();
+        self.hdt.write(w)
+    }
+}
+
+impl Graph for HdtStore {
+    type Triple<'a> = [SimpleTerm<'a>; 3];
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<Self> {
+        Box::new(self.triples_with_pattern(None, None, None).map(|t| Ok(sophia_triple(t))))
+    }
+
+    /// Constant matchers are used as a triple pattern, other matchers filter the results.
+    fn triples_matching<'s, S, P, O>(&'s self, sm: S, pm: P, om: O) -> GTripleSource<'s, Self>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+    {
+        let pattern = string_pattern(&sm, &pm, &om);
+        let [sp, pp, op] = pattern.each_ref().map(Option::as_deref);
+        Box::new(filter_matching(self.triples_with_pattern(sp, pp, op), &pattern, sm, pm, om))
+    }
+}
+
+impl MutableGraph for HdtStore {
+    type MutationError = Infallible;
+
+    fn insert<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> MgResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let [s, p, o] = [s.as_simple(), p.as_simple(), o.as_simple()].map(|t| term_string(&t));
+        Ok(HdtStore::insert(self, &s, &p, &o))
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> MgResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let [s, p, o] = [s.as_simple(), p.as_simple(), o.as_simple()].map(|t| term_string(&t));
+        Ok(HdtStore::remove(self, &s, &p, &o))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{init, load_snikmeta, snikmeta, sorted};
+    use pretty_assertions::assert_eq;
+    use sophia::api::term::matcher::Any;
+    use sophia::api::term::IriRef;
+
+    #[test]
+    fn store() {
+        init();
+        let expected = sorted(snikmeta());
+        let header = &snikmeta().header.body;
+        let mut store = HdtStore::new(load_snikmeta());
+        let top = "http://www.snik.eu/ontology/meta/Top";
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let (old, new) = ("\"top class\"@en", "\"Oberklasse\"@de");
+        assert_eq!(327, store.len());
+        assert!(!store.insert(top, label, old));
+        assert!(store.insert(top, label, new));
+        assert!(!store.insert(top, label, new));
+        assert!(store.remove(top, label, old));
+        assert!(!store.remove(top, label, old));
+        assert!(store.is_modified());
+        assert_eq!(327, store.len());
+        assert!(store.contains(top, label, new));
+        assert!(!store.contains(top, label, old));
+        let labels: Vec<_> =
+            store.triples_with_pattern(Some(top), Some(label), None).map(|t| t.2.to_string()).collect();
+        assert_eq!(vec![new.to_owned()], labels);
+        assert_eq!(1, store.triples_with_pattern(None, None, Some(new)).count());
+        // removing an inserted triple and inserting a removed one undoes the changes
+        assert!(store.remove(top, label, new));
+        assert!(store.insert(top, label, old));
+        assert!(!store.is_modified());
+        store.compact();
+        assert_eq!(expected, sorted(store.hdt()));
+
+        store.insert(top, label, new);
+        store.remove(top, label, old);
+        let mut written = Vec::new();
+        store.write(&mut written).unwrap();
+        assert!(!store.is_modified());
+        assert_eq!(327, store.len());
+        assert!(store.contains(top, label, new));
+        assert!(!store.contains(top, label, old));
+        assert_eq!(header, &store.hdt().header.body);
+        let reloaded = Hdt::from_bytes(&written).unwrap();
+        assert_eq!(sorted(store.hdt()), sorted(&reloaded));
+        assert_eq!(header, &reloaded.header.body);
+    }
+
+    #[test]
+    fn mutable_graph() {
+        init();
+        let mut store = HdtStore::new(load_snikmeta());
+        let s = SimpleTerm::Iri(IriRef::new_unchecked("http://www.snik.eu/ontology/meta/Top".into()));
+        let p = SimpleTerm::Iri(IriRef::new_unchecked("http://example.org/p".into()));
+        let o = SimpleTerm::BlankNode(sophia::api::term::BnodeId::new_unchecked("b1".into()));
+        assert!(MutableGraph::insert(&mut store, &s, &p, &o).unwrap());
+        assert_eq!(328, store.triples().count());
+        assert!(store.contains("http://www.snik.eu/ontology/meta/Top", "http://example.org/p", "_:b1"));
+        let matching: Vec<_> = store.triples_matching(Any, Some(&p), Any).map(Result::unwrap).collect();
+        assert_eq!(vec![[s.clone(), p.clone(), o.clone()]], matching);
+        // non-constant matchers filter the results
+        let other = SimpleTerm::Iri(IriRef::new_unchecked("http://example.org/other".into()));
+        assert_eq!(1, store.triples_matching(Any, Any, [o.clone(), other.clone()]).count());
+        assert_eq!(0, store.triples_matching(Any, [other.clone(), other], Any).count());
+        assert!(MutableGraph::remove(&mut store, &s, &p, &o).unwrap());
+        assert_eq!(327, store.triples().count());
+    }
+}
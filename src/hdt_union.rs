@@ -0,0 +1,262 @@
+//! Federated view of several HDT files as a single graph.
+use crate::hdt::{Hdt, StringTriple};
+use crate::hdt_graph::{filter_matching, sophia_triple, string_pattern};
+use crate::triples::TripleId;
+use crate::IdKind;
+use log::error;
+use sophia::api::graph::{GTripleSource, Graph};
+use sophia::api::term::{matcher::TermMatcher, SimpleTerm};
+use sophia::api::MownStr;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::Infallible;
+use std::iter::Peekable;
+
+type Ids<'a> = Box<dyn Iterator<Item = TripleId> + Send + 'a>;
+
+/// Union of the triples of several HDT files without duplicates, for example when a knowledge graph is split by source.
+/// A file that doesn't contain all the constant terms of a triple pattern is skipped after looking them up in its dictionary.
+/// Terms use the same format as in [`Hdt::triples_with_pattern`].
+/// # Examples
+/// ```no_run
+/// let hdts = ["a.hdt", "b.hdt"].map(|path| {
+///     let file = std::fs::File::open(path).expect("error opening file");
+///     hdt::Hdt::new(std::io::BufReader::new(file)).expect("error loading HDT")
+/// });
+/// let union = hdt::HdtUnion::new(hdts.into());
+/// println!("{}", union.pattern_count(None, Some("http://www.w3.org/1999/02/22-rdf-syntax-ns#type"), None));
+/// ```
+#[derive(Debug)]
+pub struct HdtUnion {
+    hdts: Vec<Hdt>,
+}
+
+impl HdtUnion {
+    /// Union of the given HDTs.
+    pub const fn new(hdts: Vec<Hdt>) -> Self {
+        HdtUnion { hdts }
+    }
+
+    /// The HDTs of the union.
+    pub fn hdts(&self) -> &[Hdt] {
+        &self.hdts
+    }
+
+    /// Recursive size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.hdts.iter().map(Hdt::size_in_bytes).sum()
+    }
+
+    /// The HDTs that contain all constant terms of the pattern, with the pattern translated to their IDs.
+    fn matching(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> Vec<(&Hdt, TripleId)> {
+        self.hdts.iter().filter_map(|hdt| hdt.pattern_id(sp, pp, op).map(|pat| (hdt, pat))).collect()
+    }
+
+    /// Get all triples that fit the given triple pattern in any of the HDTs, where `None` stands for a variable.
+    /// The triples are returned in ascending order of their strings and each one only once.
+    pub fn triples_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> impl Iterator<Item = StringTriple<'static>> + '_ {
+        KMerge::new(self.matching(sp, pp, op).into_iter().flat_map(|(hdt, pat)| sorted_runs(hdt, pat)).collect())
+    }
+
+    /// Number of triples that fit the given triple pattern in any of the HDTs, where `None` stands for a variable.
+    /// Only fast if at most one HDT contains the constant terms, otherwise the triples are decoded to remove duplicates.
+    pub fn pattern_count(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> usize {
+        match self.matching(sp, pp, op).as_slice() {
+            [] => 0,
+            [(hdt, _)] => hdt.pattern_count(sp, pp, op),
+            _ => self.triples_with_pattern(sp, pp, op).count(),
+        }
+    }
+}
+
+/// Triples of an HDT that fit the pattern split into two runs, each in ascending order of subject and predicate strings.
+/// Triple IDs are in SPO order but only sorted by string within a dictionary section
+/// and the shared section comes before the one with the subjects that are not objects.
+fn sorted_runs(hdt: &Hdt, pat: TripleId) -> [Groups<'_>; 2] {
+    let shared = hdt.dict.shared.num_strings();
+    let runs: [Ids; 2] = if pat.subject_id == 0 && pat.predicate_id == 0 && pat.object_id != 0 {
+        // the object index is ordered by predicate before subject
+        let (mut in_shared, mut rest): (Vec<_>, Vec<_>) =
+            hdt.triples.triples_with_pattern(&pat).partition(|t| t.subject_id <= shared);
+        in_shared.sort_unstable();
+        rest.sort_unstable();
+        [Box::new(in_shared.into_iter()), Box::new(rest.into_iter())]
+    } else {
+        [true, false].map(|in_shared| -> Ids {
+            Box::new(hdt.triples.triples_with_pattern(&pat).filter(move |t| (t.subject_id <= shared) == in_shared))
+        })
+    };
+    runs.map(|ids| Groups { hdt, ids: ids.peekable() })
+}
+
+/// Triples of a run as strings grouped by subject and predicate.
+struct Groups<'a> {
+    hdt: &'a Hdt,
+    ids: Peekable<Ids<'a>>,
+}
+
+impl Iterator for Groups<'_> {
+    type Item = (String, String, Vec<String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let dict = &self.hdt.dict;
+        loop {
+            let first = self.ids.next()?;
+            let mut object_ids = vec![first.object_id];
+            while let Some(t) =
+                self.ids.next_if(|t| t.subject_id == first.subject_id && t.predicate_id == first.predicate_id)
+            {
+                object_ids.push(t.object_id);
+            }
+            let translate = || -> Result<Self::Item, crate::four_sect_dict::DictErr> {
+                let s = dict.id_to_string(first.subject_id, &IdKind::Subject)?;
+                let p = dict.id_to_string(first.predicate_id, &IdKind::Predicate)?;
+                let objects: Result<Vec<_>, _> =
+                    object_ids.iter().map(|&o| dict.id_to_string(o, &IdKind::Object)).collect();
+                Ok((s, p, objects?))
+            };
+            match translate() {
+                Ok(group) => return Some(group),
+                Err(e) => error!("Cannot translate triples starting with {first:?}: {e}"),
+            }
+        }
+    }
+}
+
+/// K-way merge of the grouped runs of all HDTs that removes duplicate triples.
+struct KMerge<'a> {
+    runs: Vec<Groups<'a>>,
+    // objects of the next group of each run, whose subject and predicate are in the heap
+    heads: Vec<Vec<String>>,
+    // subject and predicate of the next group of each run with the index of the run, smallest first
+    heap: BinaryHeap<Reverse<(String, String, usize)>>,
+    // current subject and predicate with the remaining objects
+    current: Option<(MownStr<'static>, MownStr<'static>, std::vec::IntoIter<String>)>,
+}
+
+impl<'a> KMerge<'a> {
+    fn new(runs: Vec<Groups<'a>>) -> Self {
+        let heads = vec![Vec::new(); runs.len()];
+        let mut merge = KMerge { runs, heads, heap: BinaryHeap::new(), current: None };
+        for i in 0..merge.runs.len() {
+            merge.advance(i);
+        }
+        merge
+    }
+
+    fn advance(&mut self, i: usize) {
+        if let Some((s, p, objects)) = self.runs[i].next() {
+            self.heads[i] = objects;
+            self.heap.push(Reverse((s, p, i)));
+        }
+    }
+}
+
+impl Iterator for KMerge<'_> {
+    type Item = StringTriple<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((s, p, objects)) = &mut self.current {
+                if let Some(o) = objects.next() {
+                    return Some((s.clone(), p.clone(), MownStr::from(o)));
+                }
+            }
+            let Reverse((s, p, i)) = self.heap.pop()?;
+            let mut objects = std::mem::take(&mut self.heads[i]);
+            self.advance(i);
+            while self.heap.peek().is_some_and(|Reverse((s2, p2, _))| *s2 == s && *p2 == p) {
+                let Reverse((_, _, j)) = self.heap.pop()?;
+                objects.append(&mut self.heads[j]);
+                self.advance(j);
+            }
+            objects.sort_unstable();
+            objects.dedup();
+            self.current = Some((MownStr::from(s), MownStr::from(p), objects.into_iter()));
+        }
+    }
+}
+
+impl Graph for HdtUnion {
+    type Triple<'a> = [SimpleTerm<'a>; 3];
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<Self> {
+        Box::new(self.triples_with_pattern(None, None, None).map(|t| Ok(sophia_triple(t))))
+    }
+
+    /// Constant matchers are used as a triple pattern, other matchers filter the results.
+    fn triples_matching<'s, S, P, O>(&'s self, sm: S, pm: P, om: O) -> GTripleSource<'s, Self>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+    {
+        let pattern = string_pattern(&sm, &pm, &om);
+        let [sp, pp, op] = pattern.each_ref().map(Option::as_deref);
+        Box::new(filter_matching(self.triples_with_pattern(sp, pp, op), &pattern, sm, pm, om))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{hdt, init, load_snikmeta, snikmeta, sorted};
+    use pretty_assertions::assert_eq;
+    use sophia::api::term::matcher::Any;
+    use sophia::api::term::IriRef;
+
+    fn strings(triples: impl Iterator<Item = StringTriple<'static>>) -> Vec<[String; 3]> {
+        triples.map(|(s, p, o)| [s.to_string(), p.to_string(), o.to_string()]).collect()
+    }
+
+    #[test]
+    fn union() {
+        init();
+        let expected = sorted(snikmeta());
+        let top = "http://www.snik.eu/ontology/meta/Top";
+        let (p, o) = ("http://example.org/p", "http://example.org/o");
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        // a duplicate, a new triple and a subject that is an object in the other file
+        let extra = hdt(&[[top, label, "\"top class\"@en"], [top, p, o], [o, p, top]]);
+        let union = HdtUnion::new(vec![load_snikmeta(), extra, load_snikmeta()]);
+
+        let all = strings(union.triples_with_pattern(None, None, None));
+        let mut with_extra = expected.clone();
+        with_extra.extend([[top, p, o], [o, p, top]].map(|t| t.map(str::to_owned)));
+        with_extra.sort();
+        assert_eq!(with_extra, all);
+        assert_eq!(329, union.pattern_count(None, None, None));
+        assert_eq!(329, union.triples().count());
+        // only in one file
+        assert_eq!(2, union.pattern_count(None, Some(p), None));
+        assert_eq!(vec![[o, p, top].map(str::to_owned)], strings(union.triples_with_pattern(Some(o), None, None)));
+        assert_eq!(0, union.pattern_count(Some("doesnotexist"), None, None));
+        // every pattern type agrees with filtering all triples
+        for [s, p, o] in [[top, label, "\"top class\"@en"], [o, p, top], [top, p, o]] {
+            for pattern in [
+                [Some(s), Some(p), Some(o)],
+                [Some(s), Some(p), None],
+                [Some(s), None, Some(o)],
+                [Some(s), None, None],
+                [None, Some(p), Some(o)],
+                [None, Some(p), None],
+                [None, None, Some(o)],
+            ] {
+                let filtered: Vec<_> = all
+                    .iter()
+                    .filter(|t| pattern.iter().zip(t.iter()).all(|(x, term)| x.is_none_or(|x| x == term)))
+                    .cloned()
+                    .collect();
+                let [sp, pp, op] = pattern;
+                assert_eq!(filtered, strings(union.triples_with_pattern(sp, pp, op)), "{pattern:?}");
+                assert_eq!(filtered.len(), union.pattern_count(sp, pp, op), "{pattern:?}");
+            }
+        }
+        // Sophia
+        let s = SimpleTerm::Iri(IriRef::new_unchecked(top.into()));
+        assert_eq!(union.pattern_count(Some(top), None, None), union.triples_matching(Some(&s), Any, Any).count());
+    }
+}
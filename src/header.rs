@@ -1,9 +1,10 @@
 use crate::containers::rdf::{Id, Literal, Term, Triple};
-use crate::containers::ControlInfo;
+use crate::containers::{ControlInfo, ControlType};
+use crate::export::write_term;
 use ntriple::parser::triple_line;
 use std::collections::BTreeSet;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::str;
 
 /// Metadata about the dataset, see <https://www.rdfhdt.org/hdt-binary-format/#header>.
@@ -17,6 +18,13 @@ pub struct Header {
     pub body: BTreeSet<Triple>,
 }
 
+impl Default for Header {
+    /// Empty header in the only supported format.
+    fn default() -> Self {
+        Header { format: "ntriples".to_owned(), length: 0, body: BTreeSet::new() }
+    }
+}
+
 impl Header {
     /// Reader needs to be positioned directly after the global control information.
     pub fn read<R: BufRead>(reader: &mut R) -> io::Result<Self> {
@@ -68,6 +76,24 @@ impl Header {
             Err(Error::new(InvalidData, "Header is missing header length."))
         }
     }
+
+    /// Write the header including control information in the format expected by [`Self::read`].
+    /// The length is calculated from the body.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut body = Vec::new();
+        for triple in &self.body {
+            write_term(&mut body, triple.subject.to_hdt_string().as_bytes())?;
+            body.push(b' ');
+            write_term(&mut body, triple.predicate.as_bytes())?;
+            body.push(b' ');
+            write_term(&mut body, triple.object.to_hdt_string().as_bytes())?;
+            body.extend_from_slice(b" .\n");
+        }
+        let mut header_ci = ControlInfo::new(ControlType::Header, &self.format);
+        header_ci.set("length", body.len());
+        header_ci.write(w)?;
+        w.write_all(&body)
+    }
 }
 
 #[cfg(test)]
@@ -92,4 +118,20 @@ mod tests {
             panic!("Failed to read header");
         }
     }
+
+    #[test]
+    fn write_header() {
+        init();
+        let file = File::open("tests/resources/yago_header.hdt").expect("error opening file");
+        let mut reader = BufReader::new(file);
+        ControlInfo::read(&mut reader).expect("error reading control info");
+        let header = Header::read(&mut reader).unwrap();
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        let read = Header::read(&mut &written[..]).unwrap();
+        assert_eq!(header.body, read.body);
+        let mut written = Vec::new();
+        Header::default().write(&mut written).unwrap();
+        assert!(Header::read(&mut &written[..]).unwrap().body.is_empty());
+    }
 }
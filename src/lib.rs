@@ -8,7 +8,11 @@
 //!
 //! HDT is a loading and triple pattern querying library for the [Header Dictionary Triples](https://www.rdfhdt.org/) compressed binary RDF format.
 //!
-//! Currently this library only supports loading and querying existing HDT files as created by [hdt-cpp](https://github.com/rdfhdt/hdt-cpp).
+//! Currently this library supports loading and querying existing HDT files as created by [hdt-cpp](https://github.com/rdfhdt/hdt-cpp)
+//! as well as merging them with [`cat`], subtracting them with [`diff`], editing them with [`HdtStore`],
+//! querying several of them as one graph with [`HdtUnion`] and writing the results with [`Hdt::write`],
+//! but not converting RDF into HDT.
+//! Named graphs are supported with [`HdtQuads`] for HDTQ, the quad extension of hdt-java, and with [`HdtDataset`] for one HDT file per graph.
 //! For reference implementations of HDT in C++ and Java, which support conversion and serialization from and into HDT with different format options,
 //! and acknowledgement of all the original authors, please look at the <https://github.com/rdfhdt> organisation.
 //!
@@ -45,6 +49,14 @@
 //
 // - **`sophia`** *(enabled by default)* — Implements the Graph trait from the [Sophia](https://crates.io/crates/sophia) RDF toolkit.
 // This allows you to drastically reduce the RAM usage of an existing application based on Sophia that loads a large knowledge base but requires an input file in the HDT format.
+// - **`rayon`** — Parallel iterators over triples and parallel construction of the object index when loading.
+// - **`capi`** — C interface for use from other languages, with the header file `include/hdt.h`.
+// Build the library with `cargo rustc --release --lib --features capi --crate-type staticlib` or `--crate-type cdylib`.
+// - **`cli`** — The `hdt` command line tool with the subcommands `info`, `search`, `count` and `dump`, install with `cargo install hdt --features cli`.
+// - **`python`** — Python bindings using [PyO3](https://crates.io/crates/pyo3), built with [maturin](https://www.maturin.rs/) as the `hdt` module.
+// maturin passes the cdylib crate type itself.
+// - **`wasm`** — JavaScript bindings using [wasm-bindgen](https://crates.io/crates/wasm-bindgen) for the `wasm32-unknown-unknown` target.
+// Build with `cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib`, then run `wasm-bindgen` on the output.
 #![feature(round_char_boundary)]
 #![feature(int_roundings)]
 #![warn(missing_docs)]
@@ -73,9 +85,45 @@ pub mod hdt;
 pub mod hdt_graph;
 /// Types for representing the header.
 pub mod header;
+/// Cache for decoded terms.
+pub mod term_cache;
 /// Types for representing and querying triples.
 pub mod triples;
 
+/// Serialization into RDF text formats.
+pub mod export;
+// Building new HDT files from existing ones.
+mod builder;
+// Merging of HDT files.
+mod cat;
+pub use cat::cat;
+// Subtraction of HDT files.
+mod diff;
+pub use diff::diff;
+// Mutable graph on top of an HDT.
+mod hdt_store;
+pub use hdt_store::HdtStore;
+// Union of several HDT files.
+mod hdt_union;
+pub use hdt_union::HdtUnion;
+// Named graphs in HDTQ files.
+mod hdt_quads;
+pub use hdt_quads::HdtQuads;
+// Sophia dataset of several HDT files.
+mod hdt_dataset;
+pub use hdt_dataset::HdtDataset;
+
+// bindings for other languages
+/// C interface, see `include/hdt.h`.
+#[cfg(feature = "capi")]
+pub mod capi;
+// Python bindings.
+#[cfg(feature = "python")]
+mod python;
+/// JavaScript bindings for WebAssembly.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub use crate::hdt::Hdt;
 use containers::ControlInfo;
 use dict_sect_pfc::DictSectPFC;
@@ -87,13 +135,40 @@ pub use hdt_graph::HdtGraph;
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Once;
+    use crate::Hdt;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::sync::{Once, OnceLock};
 
     static INIT: Once = Once::new();
+    static SNIKMETA: OnceLock<Hdt> = OnceLock::new();
 
     pub fn init() {
         INIT.call_once(|| {
             env_logger::init();
         });
     }
+
+    /// The snikmeta test file, loaded on first use and shared by all tests.
+    pub fn snikmeta() -> &'static Hdt {
+        SNIKMETA.get_or_init(load_snikmeta)
+    }
+
+    /// A separate copy of the snikmeta test file for tests that need to own or modify it.
+    pub fn load_snikmeta() -> Hdt {
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        Hdt::new(BufReader::new(file)).unwrap()
+    }
+
+    /// HDT with the given triples in dictionary format.
+    pub fn hdt(triples: &[[&str; 3]]) -> Hdt {
+        crate::builder::hdt_from_triples(triples)
+    }
+
+    /// Triples of an HDT as strings in ascending order.
+    pub fn sorted(hdt: &Hdt) -> Vec<[String; 3]> {
+        let mut v: Vec<_> = hdt.triples().map(|(s, p, o)| [s.to_string(), p.to_string(), o.to_string()]).collect();
+        v.sort();
+        v
+    }
 }
@@ -0,0 +1,128 @@
+use crate::hdt::OwnedTriples;
+use crate::{Hdt, IdKind};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+type Triple = (String, String, String);
+
+/// Load the HDT file at the given path.
+#[pyfunction]
+fn open(path: &str) -> PyResult<PyHdt> {
+    let hdt = Hdt::new(BufReader::new(File::open(path)?))?;
+    Ok(PyHdt { hdt: Arc::new(hdt) })
+}
+
+/// HDT file loaded into memory.
+/// Terms are strings in the same format as in [`Hdt::triples_with_pattern`].
+#[pyclass(name = "Hdt", frozen)]
+struct PyHdt {
+    hdt: Arc<Hdt>,
+}
+
+fn id_kind(kind: &str) -> PyResult<&'static IdKind> {
+    match kind {
+        "subject" => Ok(&IdKind::Subject),
+        "predicate" => Ok(&IdKind::Predicate),
+        "object" => Ok(&IdKind::Object),
+        _ => Err(PyValueError::new_err(format!("unknown kind {kind}, use subject, predicate or object"))),
+    }
+}
+
+#[pymethods]
+impl PyHdt {
+    /// Lazy iterator over all triples that fit the given triple pattern, where None stands for a variable.
+    #[pyo3(signature = (s = None, p = None, o = None))]
+    fn search(&self, s: Option<&str>, p: Option<&str>, o: Option<&str>) -> TripleIterator {
+        TripleIterator { triples: self.hdt.clone().triples_with_pattern_owned(s, p, o) }
+    }
+
+    /// Lazy iterator over the IDs of all triples that fit the given triple pattern, where None stands for a variable.
+    #[pyo3(signature = (s = None, p = None, o = None))]
+    fn search_ids(&self, s: Option<&str>, p: Option<&str>, o: Option<&str>) -> TripleIdIterator {
+        TripleIdIterator { triples: self.hdt.clone().triples_with_pattern_owned(s, p, o) }
+    }
+
+    /// Number of triples that fit the given triple pattern, where None stands for a variable.
+    #[pyo3(signature = (s = None, p = None, o = None))]
+    fn count(&self, s: Option<&str>, p: Option<&str>, o: Option<&str>) -> usize {
+        self.hdt.pattern_count(s, p, o)
+    }
+
+    /// ID of the given term as "subject", "predicate" or "object", 0 if it is not in the dictionary.
+    fn string_to_id(&self, term: &str, kind: &str) -> PyResult<usize> {
+        Ok(self.hdt.dict.string_to_id(term, id_kind(kind)?))
+    }
+
+    /// Term with the given ID as "subject", "predicate" or "object".
+    fn id_to_string(&self, id: usize, kind: &str) -> PyResult<String> {
+        self.hdt.dict.id_to_string(id, id_kind(kind)?).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Triples of the header, which contains metadata about the dataset.
+    #[getter]
+    fn header(&self) -> Vec<Triple> {
+        let body = &self.hdt.header.body;
+        body.iter().map(|t| (t.subject.to_hdt_string(), t.predicate.clone(), t.object.to_hdt_string())).collect()
+    }
+
+    /// Size in bytes of the loaded HDT in memory.
+    fn size_in_bytes(&self) -> usize {
+        self.hdt.size_in_bytes()
+    }
+
+    fn __len__(&self) -> usize {
+        self.hdt.pattern_count(None, None, None)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Hdt with {} triples>", self.__len__())
+    }
+}
+
+/// Iterator over triples as tuples of strings, decoded one at a time.
+#[pyclass]
+struct TripleIterator {
+    triples: OwnedTriples,
+}
+
+#[pymethods]
+impl TripleIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Triple> {
+        slf.triples.next().map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string()))
+    }
+}
+
+/// Iterator over triple IDs as tuples of integers.
+#[pyclass]
+struct TripleIdIterator {
+    triples: OwnedTriples,
+}
+
+#[pymethods]
+impl TripleIdIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(usize, usize, usize)> {
+        slf.triples.next_id().map(|t| (t.subject_id, t.predicate_id, t.object_id))
+    }
+}
+
+/// Python module `hdt`.
+#[pymodule]
+#[pyo3(name = "hdt")]
+fn hdt_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_class::<PyHdt>()?;
+    m.add_class::<TripleIterator>()?;
+    m.add_class::<TripleIdIterator>()?;
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Bounded cache for decoded dictionary terms.
+use crate::four_sect_dict::IdKind;
+use crate::triples::Id;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Number of cache hits and misses since the creation of a [`TermCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that were answered from the cache.
+    pub hits: u64,
+    /// Lookups that had to decode the term from the dictionary.
+    pub misses: u64,
+}
+
+/// Cache of at most `capacity` decoded terms keyed by ID kind and ID.
+/// Uses the CLOCK replacement policy, an approximation of LRU where a hit only sets a flag.
+/// Terms are shared as `Arc<str>` so that hot terms like common predicates and classes are decoded only once.
+/// The keys are spread over several shards so that parallel readers rarely wait for each other,
+/// and a hit only takes the read lock of its shard.
+pub struct TermCache {
+    shards: Box<[RwLock<Clock>]>,
+}
+
+struct Slot {
+    key: (IdKind, Id),
+    value: Arc<str>,
+    referenced: AtomicBool,
+}
+
+struct Clock {
+    capacity: usize,
+    index: HashMap<(IdKind, Id), usize>,
+    slots: Vec<Slot>,
+    hand: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl fmt::Debug for TermCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let capacity: usize = self.shards.iter().map(|shard| shard.read().unwrap().capacity).sum();
+        write!(f, "{} of {capacity} terms cached in {} shards, {:?}", self.len(), self.shards.len(), self.stats())
+    }
+}
+
+impl TermCache {
+    /// Upper limit for the number of shards.
+    const MAX_SHARDS: usize = 16;
+    /// Smallest capacity of a shard, smaller caches have fewer shards.
+    const MIN_SHARD_CAPACITY: usize = 64;
+
+    /// Create an empty cache that holds at most `capacity` terms.
+    pub fn new(capacity: usize) -> Self {
+        let num_shards = (capacity / Self::MIN_SHARD_CAPACITY).clamp(1, Self::MAX_SHARDS);
+        let shards = (0..num_shards)
+            .map(|i| {
+                // the first shards hold one more term if the capacity isn't divisible by the number of shards
+                let capacity = capacity / num_shards + usize::from(i < capacity % num_shards);
+                RwLock::new(Clock {
+                    capacity,
+                    index: HashMap::with_capacity(capacity),
+                    slots: Vec::with_capacity(capacity),
+                    hand: 0,
+                    hits: AtomicU64::new(0),
+                    misses: AtomicU64::new(0),
+                })
+            })
+            .collect();
+        TermCache { shards }
+    }
+
+    fn shard(&self, key: (IdKind, Id)) -> &RwLock<Clock> {
+        // Fibonacci hashing spreads consecutive IDs, which are typical for scans, over all shards
+        let hash = (key.1 as u64 ^ ((key.0 as u64) << 62)).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        &self.shards[(hash >> 32) as usize % self.shards.len()]
+    }
+
+    /// Get the term with the given key from the cache or decode and insert it using the given function.
+    /// No lock is held while decoding so that other threads can use the cache in the meantime.
+    pub fn get_or_insert_with<E>(
+        &self, key: (IdKind, Id), decode: impl FnOnce() -> Result<String, E>,
+    ) -> Result<Arc<str>, E> {
+        let shard = self.shard(key);
+        {
+            let clock = shard.read().unwrap();
+            if let Some(&i) = clock.index.get(&key) {
+                clock.hits.fetch_add(1, Ordering::Relaxed);
+                let slot = &clock.slots[i];
+                slot.referenced.store(true, Ordering::Relaxed);
+                return Ok(slot.value.clone());
+            }
+            clock.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        let value: Arc<str> = decode()?.into();
+        shard.write().unwrap().insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Hit and miss counts since the creation of the cache.
+    pub fn stats(&self) -> CacheStats {
+        self.shards.iter().fold(CacheStats::default(), |stats, shard| {
+            let clock = shard.read().unwrap();
+            CacheStats {
+                hits: stats.hits + clock.hits.load(Ordering::Relaxed),
+                misses: stats.misses + clock.misses.load(Ordering::Relaxed),
+            }
+        })
+    }
+
+    /// Number of cached terms.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().slots.len()).sum()
+    }
+
+    /// Whether no terms are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Clock {
+    fn insert(&mut self, key: (IdKind, Id), value: Arc<str>) {
+        // another thread may have inserted the same term while it was decoded
+        if self.capacity == 0 || self.index.contains_key(&key) {
+            return;
+        }
+        if self.slots.len() < self.capacity {
+            self.index.insert(key, self.slots.len());
+            self.slots.push(Slot { key, value, referenced: AtomicBool::new(false) });
+            return;
+        }
+        // give referenced slots a second chance until an unreferenced one is found
+        while *self.slots[self.hand].referenced.get_mut() {
+            *self.slots[self.hand].referenced.get_mut() = false;
+            self.hand = (self.hand + 1) % self.capacity;
+        }
+        let slot = &mut self.slots[self.hand];
+        self.index.remove(&slot.key);
+        self.index.insert(key, self.hand);
+        *slot = Slot { key, value, referenced: AtomicBool::new(false) };
+        self.hand = (self.hand + 1) % self.capacity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::convert::Infallible;
+
+    #[test]
+    fn clock() {
+        init();
+        let cache = TermCache::new(2);
+        let get =
+            |id: Id| cache.get_or_insert_with::<Infallible>((IdKind::Object, id), || Ok(id.to_string())).unwrap();
+        assert_eq!("1", &*get(1));
+        assert_eq!("2", &*get(2));
+        assert_eq!("1", &*get(1));
+        assert_eq!(CacheStats { hits: 1, misses: 2 }, cache.stats());
+        // 1 is referenced and gets a second chance, so 2 is evicted
+        get(3);
+        assert_eq!(2, cache.len());
+        get(1);
+        assert_eq!(CacheStats { hits: 2, misses: 3 }, cache.stats());
+        get(2);
+        assert_eq!(CacheStats { hits: 2, misses: 4 }, cache.stats());
+        // same ID but different kind is a different key
+        cache.get_or_insert_with::<Infallible>((IdKind::Predicate, 1), || Ok("p".to_owned())).unwrap();
+        assert_eq!(CacheStats { hits: 2, misses: 5 }, cache.stats());
+
+        let empty = TermCache::new(0);
+        empty.get_or_insert_with::<Infallible>((IdKind::Subject, 1), || Ok("s".to_owned())).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn shards() {
+        init();
+        let capacity = 2000;
+        let cache = TermCache::new(capacity);
+        assert_eq!(TermCache::MAX_SHARDS, cache.shards.len());
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for id in 1..=2 * capacity {
+                        let term =
+                            cache.get_or_insert_with::<Infallible>((IdKind::Subject, id), || Ok(id.to_string()));
+                        assert_eq!(id.to_string(), &*term.unwrap());
+                    }
+                });
+            }
+        });
+        assert_eq!(capacity, cache.len());
+        let stats = cache.stats();
+        assert_eq!(4 * 2 * capacity as u64, stats.hits + stats.misses);
+    }
+}
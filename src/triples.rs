@@ -1,4 +1,4 @@
-use crate::containers::{AdjList, Bitmap, Sequence};
+use crate::containers::{AdjList, Bitmap, ControlType, Deferred, Sequence};
 use crate::ControlInfo;
 use bytesize::ByteSize;
 use log::{debug, error};
@@ -7,7 +7,8 @@ use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+use std::mem::size_of;
 use sucds::{CompactVector, Searial, WaveletMatrix, WaveletMatrixBuilder};
 
 mod subject_iter;
@@ -51,6 +52,36 @@ impl TryFrom<u32> for Order {
     }
 }
 
+/// Unsigned integer type for the temporary positions when building the [`OpIndex`].
+/// `u32` halves the memory usage and is used for files with less than 2^32 triples, `usize` otherwise.
+trait Position: Copy + Default + Send + Sync {
+    fn from_usize(x: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+impl Position for u32 {
+    fn from_usize(x: usize) -> Self {
+        x as u32
+    }
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl Position for usize {
+    fn from_usize(x: usize) -> Self {
+        x
+    }
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+/// Number of bits needed for the largest of the given values, at least one because the wavelet matrix cannot have a width of 0.
+fn bits_for(values: &[usize]) -> usize {
+    (usize::BITS - values.iter().max().unwrap_or(&0).leading_zeros()).max(1) as usize
+}
+
 /// Inverse index from object id to positions in the object adjacency list.
 /// Used for logarithmic (?) time access instead of linear time sequential search.
 pub struct OpIndex {
@@ -169,13 +200,47 @@ impl TriplesBitmap {
         self.bin_search_y(property_id, self.find_y(subject_id), self.last_y(subject_id) + 1)
     }
 
+    /// Position of the given triple in the object layer, which is its index in SPO order, `None` if it doesn't exist.
+    pub fn position(&self, t: &TripleId) -> Option<usize> {
+        if t.subject_id == 0 {
+            return None;
+        }
+        let pos_y = self.search_y(t.subject_id - 1, t.predicate_id)?;
+        self.adjlist_z.search(pos_y, t.object_id)
+    }
+
+    /// Triple ID at the given position in SPO order, the inverse of [`Self::position`].
+    pub fn triple_at(&self, pos_z: usize) -> TripleId {
+        let pos_y = self.adjlist_z.bitmap.dict.rank(pos_z as u64, true);
+        let s = self.bitmap_y.dict.rank(pos_y, true) as Id + 1;
+        let p = self.wavelet_y.get(pos_y as usize) as Id;
+        TripleId::new(s, p, self.adjlist_z.get_id(pos_z))
+    }
+
+    /// Iterator over all triple IDs fitting the given triple pattern, where 0 stands for a variable.
+    /// Uses the appropriate iterator for the pattern, such as [`PredicateIter`] for ?P?.
+    pub fn triples_with_pattern(&self, pat: &TripleId) -> Box<dyn Iterator<Item = TripleId> + Send + '_> {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        match (s, p, o) {
+            (0, 0, 0) => Box::new(SubjectIter::new(self)),
+            (0, 0, o) => Box::new(ObjectIter::new(self, o)),
+            (0, p, 0) => Box::new(PredicateIter::new(self, p)),
+            (0, p, o) => Box::new(PredicateObjectIter::new(self, p, o).map(move |s| TripleId::new(s, p, o))),
+            // SPO, SP?, S?O and S??
+            _ => Box::new(SubjectIter::with_pattern(self, pat)),
+        }
+    }
+
     fn build_wavelet(mut sequence: Sequence) -> WaveletMatrix {
         debug!("Building wavelet matrix...");
         let mut wavelet_builder = WaveletMatrixBuilder::with_width(sequence.bits_per_entry);
         for x in &sequence {
             wavelet_builder.push(x);
         }
-        assert!(sequence.crc_handle.take().unwrap().join().unwrap(), "wavelet source CRC check failed.");
+        // sequences built in memory have no checksum
+        if let Some(crc_handle) = sequence.crc_handle.take() {
+            assert!(crc_handle.join().unwrap(), "wavelet source CRC check failed.");
+        }
         drop(sequence);
         let wavelet = wavelet_builder.build().expect("Error building the wavelet matrix. Aborting.");
         debug!("built wavelet matrix with length {}", wavelet.len());
@@ -190,7 +255,113 @@ impl TriplesBitmap {
     }
     */
 
-    fn read<R: BufRead>(reader: &mut R, triples_ci: &ControlInfo) -> io::Result<Self> {
+    /// Counting sort of the positions in the predicate layer by the object they point to.
+    /// Returns the sorted positions and the start offset of each object,
+    /// so that the positions of object `o` are `positions[offsets[o]..offsets[o + 1]]`.
+    // In https://github.com/rdfhdt/hdt-cpp/blob/develop/libhdt/src/triples/BitmapTriples.cpp
+    // they also count the number of appearances first, which avoids a vector per object.
+    fn group_by_object<P: Position>(sequence_z: &Sequence, bitmap_z: &Bitmap) -> (Vec<P>, Vec<usize>) {
+        let max_object = sequence_z.into_iter().max().unwrap_or(0);
+        // count the appearances of each object, offsets[0] stays 0 as object IDs start at 1
+        let mut offsets = vec![0; max_object + 2];
+        for object in sequence_z {
+            if object == 0 {
+                error!("ERROR: There is a zero value in the Z level.");
+                continue;
+            }
+            offsets[object] += 1;
+        }
+        // prefix sum, now offsets[o] is the end of the positions of object o
+        for o in 1..offsets.len() {
+            offsets[o] += offsets[o - 1];
+        }
+        // Fill backwards so that the positions of each object stay in ascending order.
+        // This moves offsets[o] from the end to the start of the positions of object o.
+        let mut positions = vec![P::default(); offsets[max_object + 1]];
+        let Some(last_z) = sequence_z.entries.checked_sub(1) else {
+            return (positions, offsets);
+        };
+        let mut pos_y = bitmap_z.dict.rank(last_z as u64, true) as usize;
+        for pos_z in (0..=last_z).rev() {
+            if pos_z < last_z && bitmap_z.at_last_sibling(pos_z) {
+                pos_y -= 1;
+            }
+            let object = sequence_z.get(pos_z);
+            if object == 0 {
+                continue;
+            }
+            offsets[object] -= 1;
+            positions[offsets[object]] = P::from_usize(pos_y);
+        }
+        (positions, offsets)
+    }
+
+    /// Sort the positions of each object with the given function, splitting the objects among threads.
+    #[cfg(feature = "rayon")]
+    fn par_sort_groups<P: Position>(positions: &mut [P], offsets: &[usize], sort: &(impl Fn(&mut [P]) + Sync)) {
+        // offsets are absolute but the positions may be a subslice starting at offsets[0]
+        let base = offsets[0];
+        if positions.len() <= 1 << 16 || offsets.len() <= 2 {
+            for w in offsets.windows(2) {
+                sort(&mut positions[w[0] - base..w[1] - base]);
+            }
+            return;
+        }
+        let mid = offsets.len() / 2;
+        let (left, right) = positions.split_at_mut(offsets[mid] - base);
+        rayon::join(
+            || Self::par_sort_groups(left, &offsets[..=mid], sort),
+            || Self::par_sort_groups(right, &offsets[mid..], sort),
+        );
+    }
+
+    /// Build the object-based index using `P` for the temporary positions in the predicate layer.
+    /// Joins the wavelet matrix thread when the wavelet matrix is needed for sorting by predicate.
+    fn build_op_index<P: Position>(
+        sequence_z: &Sequence, bitmap_z: &Bitmap, wavelet_thread: Deferred<WaveletMatrix>,
+    ) -> (OpIndex, WaveletMatrix) {
+        // the grouping by object doesn't need the wavelet matrix and runs while it is being built
+        debug!("Building OPS index...");
+        let (mut positions, offsets) = Self::group_by_object::<P>(sequence_z, bitmap_z);
+        let wavelet_y = wavelet_thread.join().unwrap();
+        // the cached keys only need temporary memory for the objects that are currently being sorted
+        let sort = |group: &mut [P]| group.sort_by_cached_key(|&pos_y| wavelet_y.get(pos_y.to_usize()));
+        #[cfg(feature = "rayon")]
+        Self::par_sort_groups(&mut positions, &offsets, &sort);
+        #[cfg(not(feature = "rayon"))]
+        for w in offsets.windows(2) {
+            sort(&mut positions[w[0]..w[1]]);
+        }
+        // reduce memory consumption of index by using adjacency list
+        // The bitmap is built first so that the offsets can be freed before the compact vector is allocated,
+        // which keeps the peak below the temporary arrays plus the final index.
+        let temp_size = positions.len() * size_of::<P>() + offsets.len() * size_of::<usize>();
+        let mut bitmap_index_dict = RsDict::new();
+        for w in offsets.windows(2) {
+            for pos in w[0]..w[1] {
+                bitmap_index_dict.push(pos == w[0]);
+            }
+        }
+        drop(offsets);
+        let bitmap_index = Bitmap { dict: bitmap_index_dict };
+        let mut cv = CompactVector::with_capacity(positions.len(), sucds::util::needed_bits(sequence_z.entries));
+        for &pos_y in &positions {
+            cv.push(pos_y.to_usize());
+        }
+        // estimated from the array sizes, allocator overhead and the wavelet matrix are not included
+        let estimated_peak = (temp_size + bitmap_index.size_in_bytes())
+            .max(positions.len() * size_of::<P>() + bitmap_index.size_in_bytes() + cv.len() * cv.width() / 8);
+        drop(positions);
+        let op_index = OpIndex { sequence: cv, bitmap: bitmap_index };
+        debug!(
+            "built OPS index of {} with an estimated peak of {} for temporary arrays and index",
+            ByteSize(op_index.size_in_bytes() as u64),
+            ByteSize(estimated_peak as u64)
+        );
+        (op_index, wavelet_y)
+    }
+
+    pub(crate) fn read<R: BufRead>(reader: &mut R, triples_ci: &ControlInfo) -> io::Result<Self> {
         use std::io::Error;
         use std::io::ErrorKind::InvalidData;
 
@@ -208,59 +379,91 @@ impl TriplesBitmap {
 
         // read sequences
         let sequence_y = Sequence::read(reader)?;
-        let wavelet_thread = std::thread::spawn(|| Self::build_wavelet(sequence_y));
+        let wavelet_thread = Deferred::spawn(|| Self::build_wavelet(sequence_y));
         let mut sequence_z = Sequence::read(reader)?;
 
-        // construct adjacency lists
         // construct object-based index to traverse from the leaves and support ??O and ?PO queries
-        debug!("Building OPS index...");
-        let entries = sequence_z.entries;
-        // if it takes too long to calculate, can also pass in as parameter
-        let max_object = sequence_z.into_iter().max().unwrap().to_owned();
-        // limited to < 2^32 objects
-        let mut indicess = vec![Vec::<u32>::with_capacity(4); max_object];
-
-        // Count the indexes of appearance of each object
-        // In https://github.com/rdfhdt/hdt-cpp/blob/develop/libhdt/src/triples/BitmapTriples.cpp
-        // they count the number of appearances in a sequence instead, which saves memory
-        // temporarily but they need to loop over it an additional time.
-        for pos_z in 0..entries {
-            let object = sequence_z.get(pos_z);
-            if object == 0 {
-                error!("ERROR: There is a zero value in the Z level.");
-                continue;
-            }
-            let pos_y = bitmap_z.dict.rank(pos_z.to_owned() as u64, true);
-            indicess[object - 1].push(pos_y as u32); // hdt index counts from 1 but we count from 0 for simplicity
-        }
-        // reduce memory consumption of index by using adjacency list
-        let mut bitmap_index_dict = RsDict::new();
-        let mut cv = CompactVector::with_capacity(entries, sucds::util::needed_bits(entries));
-        let wavelet_y = wavelet_thread.join().unwrap();
-        /*
-        let get_p = |pos_z: u32| {
-            let pos_y = bitmap_z.dict.rank(pos_z.to_owned() as u64, true);
-            wavelet_y.get(pos_y as usize) as Id
+        // positions in the predicate layer are at most the number of triples
+        let (op_index, wavelet_y) = if u32::try_from(sequence_z.entries).is_ok() {
+            Self::build_op_index::<u32>(&sequence_z, &bitmap_z, wavelet_thread)
+        } else {
+            Self::build_op_index::<usize>(&sequence_z, &bitmap_z, wavelet_thread)
         };
-        */
-        for mut indices in indicess {
-            let mut first = true;
-            // sort by predicate
-            indices.sort_by_cached_key(|pos_y| wavelet_y.get(*pos_y as usize));
-            for index in indices {
-                bitmap_index_dict.push(first);
-                first = false;
-                cv.push(index as usize);
-            }
-        }
-        let bitmap_index = Bitmap { dict: bitmap_index_dict };
-        let op_index = OpIndex { sequence: cv, bitmap: bitmap_index };
-        debug!("built OPS index");
         assert!(sequence_z.crc_handle.take().unwrap().join().unwrap(), "sequence_z CRC check failed.");
         let adjlist_z = AdjList::new(sequence_z, bitmap_z);
         Ok(TriplesBitmap { order, bitmap_y, adjlist_z, op_index, wavelet_y })
     }
 
+    /// Build the triples section in SPO order from the given triple IDs, which must be sorted and unique.
+    /// Subject IDs must be consecutive starting at 1, because the format cannot represent subjects without triples.
+    pub fn from_triples(triples: impl IntoIterator<Item = TripleId>) -> Self {
+        let mut ys = Vec::new();
+        let mut zs = Vec::new();
+        // a one bit marks the last predicate of a subject and the last object of a subject and predicate
+        let mut bits_y = RsDict::new();
+        let mut bits_z = RsDict::new();
+        let mut last: Option<TripleId> = None;
+        for t in triples {
+            let new_sp = match last {
+                None => {
+                    debug_assert_eq!(1, t.subject_id, "subject IDs must start at 1");
+                    true
+                }
+                Some(l) => {
+                    debug_assert!(l < t, "triples must be sorted and unique");
+                    debug_assert!(t.subject_id <= l.subject_id + 1, "subject IDs must be consecutive");
+                    let new_sp = (l.subject_id, l.predicate_id) != (t.subject_id, t.predicate_id);
+                    bits_z.push(new_sp);
+                    if new_sp {
+                        bits_y.push(l.subject_id != t.subject_id);
+                    }
+                    new_sp
+                }
+            };
+            if new_sp {
+                ys.push(t.predicate_id);
+            }
+            zs.push(t.object_id);
+            last = Some(t);
+        }
+        if last.is_some() {
+            bits_y.push(true);
+            bits_z.push(true);
+        }
+        let sequence_y = Sequence::new(&ys, bits_for(&ys));
+        drop(ys);
+        let sequence_z = Sequence::new(&zs, bits_for(&zs));
+        drop(zs);
+        let bitmap_y = Bitmap { dict: bits_y };
+        let bitmap_z = Bitmap { dict: bits_z };
+        let wavelet_y = Deferred::Done(Self::build_wavelet(sequence_y));
+        let (op_index, wavelet_y) = if u32::try_from(sequence_z.entries).is_ok() {
+            Self::build_op_index::<u32>(&sequence_z, &bitmap_z, wavelet_y)
+        } else {
+            Self::build_op_index::<usize>(&sequence_z, &bitmap_z, wavelet_y)
+        };
+        let adjlist_z = AdjList::new(sequence_z, bitmap_z);
+        TriplesBitmap { order: Order::SPO, bitmap_y, adjlist_z, op_index, wavelet_y }
+    }
+
+    /// Write the triples section including control information in the format expected by [`Self::read_sect`].
+    /// The predicate layer is restored from the wavelet matrix.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_format(w, "<http://purl.org/HDT/hdt#triplesBitmap>")
+    }
+
+    /// Like [`Self::write`] but with the given format in the control information.
+    pub(crate) fn write_format<W: Write>(&self, w: &mut W, format: &str) -> io::Result<()> {
+        let mut triples_ci = ControlInfo::new(ControlType::Triples, format);
+        triples_ci.set("order", self.order.clone() as u8);
+        triples_ci.write(w)?;
+        let ys: Vec<usize> = (0..self.wavelet_y.len()).map(|i| self.wavelet_y.get(i)).collect();
+        self.bitmap_y.write(w, ys.len())?;
+        self.adjlist_z.bitmap.write(w, self.adjlist_z.len())?;
+        Sequence::new(&ys, bits_for(&ys)).write(w)?;
+        self.adjlist_z.sequence.write(w)
+    }
+
     /// Transform the given IDs of the layers in triple section order to a triple ID.
     /// Warning: At the moment only SPO is properly supported anyways, in which case this is equivalent to `TripleId::new(x,y,z)`.
     /// Other orders may lead to undefined behaviour.
@@ -297,8 +500,9 @@ impl<'a> IntoIterator for &'a TriplesBitmap {
 /// Subject, predicate or object ID, starting at 1.
 /// Subjects and predicate share IDs, starting at 1, for common values.
 /// A value of 0 indicates either not found (as a return value) or all of them (in a triple pattern).
-/// In the official documentation, u32 is used, however here, usize is used.
-/// While u32 caps out at 4 billion, more is not supported by the format anyways so this can probably be changed to u32.
+/// In the official documentation, u32 is used, however the format stores IDs as variable length integers and log arrays
+/// of up to 64 bits, so usize is used to support files with 2^32 or more terms on 64 bit platforms.
+/// Use [`CompactTripleId`] to store many triple IDs of files that fit into u32.
 pub type Id = usize;
 
 /// Type for a triple encoded as numeric IDs for subject, predicate and object, respectively.
@@ -320,11 +524,41 @@ impl TripleId {
     }
 }
 
+/// Triple ID with 32 bit components, which uses half the memory of a [`TripleId`] on 64 bit platforms.
+/// Only possible when all IDs are below 2^32, see [`CompactTripleId::try_from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactTripleId {
+    /// Index starting at 1 in the combined shared and subject section.
+    pub subject_id: u32,
+    /// Index starting at 1 in the predicate section.
+    pub predicate_id: u32,
+    /// Index starting at 1 in the combined shared and object section.
+    pub object_id: u32,
+}
+
+impl TryFrom<TripleId> for CompactTripleId {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(t: TripleId) -> Result<Self, Self::Error> {
+        Ok(CompactTripleId {
+            subject_id: u32::try_from(t.subject_id)?,
+            predicate_id: u32::try_from(t.predicate_id)?,
+            object_id: u32::try_from(t.object_id)?,
+        })
+    }
+}
+
+impl From<CompactTripleId> for TripleId {
+    fn from(t: CompactTripleId) -> Self {
+        TripleId::new(t.subject_id as Id, t.predicate_id as Id, t.object_id as Id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::header::Header;
-    use crate::tests::init;
+    use crate::tests::{init, snikmeta};
     use crate::{ControlInfo, FourSectDict, IdKind};
     use pretty_assertions::assert_eq;
     use std::fs::File;
@@ -355,6 +589,10 @@ mod tests {
         assert_eq!(v[0].subject_id, 1);
         assert_eq!(v[2].subject_id, 1);
         assert_eq!(v[3].subject_id, 2);
+        for (pos, t) in v.iter().enumerate() {
+            assert_eq!(Some(pos), triples.position(t));
+            assert_eq!(*t, triples.triple_at(pos));
+        }
         let num_subjects = 48;
         let num_predicates = 23;
         let num_objects = 175;
@@ -372,6 +610,25 @@ mod tests {
             }
         }
 
+        // OP index is sorted by predicate and then by position in the predicate layer for each object
+        for o in 1..=num_objects {
+            let op = &triples.op_index;
+            let keys: Vec<(usize, usize)> = (op.find(o)..=op.last(o))
+                .map(|i| op.sequence.get(i))
+                .map(|pos_y| (triples.wavelet_y.get(pos_y), pos_y))
+                .collect();
+            let mut sorted = keys.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, keys, "OP index of object {o}");
+        }
+
+        // compact IDs
+        for t in &v {
+            assert_eq!(*t, TripleId::from(CompactTripleId::try_from(*t).unwrap()));
+        }
+        #[cfg(target_pointer_width = "64")]
+        assert!(CompactTripleId::try_from(TripleId::new(1, 1, u32::MAX as Id + 1)).is_err());
+
         // SubjectIter
         assert_eq!(0, SubjectIter::empty(&triples).count());
         // SPO
@@ -393,7 +650,82 @@ mod tests {
         }
         // ??? (all triples)
         assert_eq!(v, SubjectIter::with_pattern(&triples, &TripleId::new(0, 0, 0)).collect::<Vec<_>>());
+        // subject blocks
+        for size in [1, 5, num_subjects] {
+            let blocks: Vec<TripleId> = (1..=num_subjects)
+                .step_by(size)
+                .flat_map(|start| {
+                    SubjectIter::with_subject_range(&triples, start, (start + size).min(num_subjects + 1))
+                })
+                .collect();
+            assert_eq!(v, blocks, "subject blocks of size {size}");
+        }
+        // predicate occurrence chunks
+        for p in 1..=num_predicates {
+            let occs = PredicateIter::occurrences(&triples, p);
+            let chunks: Vec<TripleId> = (0..occs)
+                .step_by(3)
+                .flat_map(|start| PredicateIter::with_range(&triples, p, start, (start + 3).min(occs)))
+                .collect();
+            assert_eq!(PredicateIter::new(&triples, p).collect::<Vec<_>>(), chunks, "predicate {p} in chunks");
+        }
         // SP? where S and P are in the graph, but not together
         assert_eq!(0, SubjectIter::with_pattern(&triples, &TripleId::new(12, 14, 154)).count());
+
+        // all patterns
+        for t in &v {
+            for pat in [
+                *t,
+                TripleId::new(t.subject_id, t.predicate_id, 0),
+                TripleId::new(t.subject_id, 0, t.object_id),
+                TripleId::new(t.subject_id, 0, 0),
+                TripleId::new(0, t.predicate_id, t.object_id),
+                TripleId::new(0, t.predicate_id, 0),
+                TripleId::new(0, 0, t.object_id),
+            ] {
+                let matches = |x: &&TripleId| {
+                    [
+                        (pat.subject_id, x.subject_id),
+                        (pat.predicate_id, x.predicate_id),
+                        (pat.object_id, x.object_id),
+                    ]
+                    .iter()
+                    .all(|(p, x)| *p == 0 || p == x)
+                };
+                let mut expected: Vec<TripleId> = v.iter().filter(matches).copied().collect();
+                let mut actual: Vec<TripleId> = triples.triples_with_pattern(&pat).collect();
+                expected.sort_unstable();
+                actual.sort_unstable();
+                assert_eq!(expected, actual, "triples_with_pattern({pat:?})");
+            }
+        }
+        assert_eq!(v, triples.triples_with_pattern(&TripleId::new(0, 0, 0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn from_triples() {
+        init();
+        let triples = &snikmeta().triples;
+        let v: Vec<TripleId> = triples.into_iter().collect();
+        let built = TriplesBitmap::from_triples(v.iter().copied());
+        // same layout as written by hdt-cpp
+        // bitmaps that were read are padded to whole blocks
+        let bits = |b: &Bitmap, len: usize| (0..len as u64).map(|i| b.dict.get_bit(i)).collect::<Vec<_>>();
+        let (len_y, len_z) = (triples.wavelet_y.len(), triples.adjlist_z.len());
+        assert_eq!(bits(&triples.bitmap_y, len_y), bits(&built.bitmap_y, len_y));
+        assert_eq!(bits(&triples.adjlist_z.bitmap, len_z), bits(&built.adjlist_z.bitmap, len_z));
+        let seq = |s: &Sequence| s.into_iter().collect::<Vec<_>>();
+        assert_eq!(seq(&triples.adjlist_z.sequence), seq(&built.adjlist_z.sequence));
+        assert_eq!(triples.wavelet_y.len(), built.wavelet_y.len());
+        for t in &v {
+            for pat in [TripleId::new(0, t.predicate_id, 0), TripleId::new(0, 0, t.object_id), *t] {
+                assert_eq!(
+                    triples.triples_with_pattern(&pat).collect::<Vec<_>>(),
+                    built.triples_with_pattern(&pat).collect::<Vec<_>>(),
+                    "triples_with_pattern({pat:?})"
+                );
+            }
+        }
+        assert_eq!(0, TriplesBitmap::from_triples([]).into_iter().count());
     }
 }
@@ -18,10 +18,22 @@ impl<'a> PredicateIter<'a> {
     /// Panics if the object does not exist.
     pub fn new(triples: &'a TriplesBitmap, p: Id) -> Self {
         assert!(p != 0, "object 0 does not exist, cant iterate");
-        let occs = triples.wavelet_y.rank(triples.wavelet_y.len(), p as usize);
+        let occs = Self::occurrences(triples, p);
         //println!("the predicate {} is used by {} subjects in the index", p, occs);
         PredicateIter { triples, p, i: 0, pos_z: 0, os: 0, s: 0, occs }
     }
+
+    /// Iterate over the triples of the `start`-th (inclusive) to `end`-th (exclusive) occurrence of the property in the predicate layer, counting from 0.
+    /// Used to split the triples with a given property into chunks that can be processed independently.
+    pub fn with_range(triples: &'a TriplesBitmap, p: Id, start: usize, end: usize) -> Self {
+        assert!(p != 0, "object 0 does not exist, cant iterate");
+        PredicateIter { triples, p, i: start, pos_z: 0, os: 0, s: 0, occs: end }
+    }
+
+    /// Number of occurrences of the given property in the predicate layer, which is the number of subjects that use it.
+    pub fn occurrences(triples: &TriplesBitmap, p: Id) -> usize {
+        triples.wavelet_y.rank(triples.wavelet_y.len(), p as usize)
+    }
 }
 
 impl<'a> Iterator for PredicateIter<'a> {
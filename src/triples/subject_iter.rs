@@ -44,6 +44,17 @@ impl<'a> SubjectIter<'a> {
         SubjectIter { triples, x: subject_id, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z: 0 }
     }
 
+    /// Iterate over all triples whose subject ID is in the range from `start` (inclusive) to `end` (exclusive).
+    /// Used to split all triples into blocks of subjects that can be processed independently.
+    pub fn with_subject_range(triples: &'a TriplesBitmap, start: Id, end: Id) -> Self {
+        assert!(start != 0 && start <= end, "invalid subject range {start}..{end}");
+        let min_y = triples.find_y(start - 1);
+        let min_z = triples.adjlist_z.find(min_y as Id);
+        let max_y = triples.find_y(end - 1);
+        let max_z = triples.adjlist_z.find(max_y as Id);
+        SubjectIter { triples, x: start, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z: 0 }
+    }
+
     /// Iterate over triples fitting the given SPO, SP? S??, S?O or ??? triple pattern.
     /// Variable positions are signified with a 0 value.
     /// Undefined result if any other triple pattern is used.
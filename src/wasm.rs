@@ -0,0 +1,41 @@
+use crate::Hdt;
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+/// HDT file loaded into memory, available in JavaScript as the class `Hdt`.
+/// Terms are passed as strings in the same format as in [`Hdt::triples_with_pattern`].
+#[wasm_bindgen(js_name = Hdt)]
+pub struct WasmHdt {
+    hdt: Hdt,
+}
+
+#[wasm_bindgen(js_class = Hdt)]
+impl WasmHdt {
+    /// Load an HDT file from its complete contents, for example `new Uint8Array(await response.arrayBuffer())`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmHdt, JsError> {
+        Ok(WasmHdt { hdt: Hdt::from_bytes(bytes)? })
+    }
+
+    /// Array of `[subject, predicate, object]` arrays that fit the given triple pattern,
+    /// where `undefined` or `null` stands for a variable.
+    #[wasm_bindgen(js_name = triplesWithPattern)]
+    pub fn triples_with_pattern(&self, s: Option<String>, p: Option<String>, o: Option<String>) -> Array {
+        self.hdt
+            .triples_with_pattern(s.as_deref(), p.as_deref(), o.as_deref())
+            .map(|(s, p, o)| Array::of3(&JsValue::from_str(&s), &JsValue::from_str(&p), &JsValue::from_str(&o)))
+            .collect()
+    }
+
+    /// Number of triples that fit the given triple pattern, see [`Hdt::pattern_count`].
+    #[wasm_bindgen(js_name = patternCount)]
+    pub fn pattern_count(&self, s: Option<String>, p: Option<String>, o: Option<String>) -> usize {
+        self.hdt.pattern_count(s.as_deref(), p.as_deref(), o.as_deref())
+    }
+
+    /// Size in bytes of the loaded HDT in WebAssembly memory.
+    #[wasm_bindgen(js_name = sizeInBytes)]
+    pub fn size_in_bytes(&self) -> usize {
+        self.hdt.size_in_bytes()
+    }
+}
@@ -0,0 +1,35 @@
+//! Builds the static library, compiles the C test program against it and runs it, requires a C compiler.
+#![cfg(all(feature = "capi", target_os = "linux"))]
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    // the crate is only built as an rlib by default, a separate target directory avoids waiting for the build lock
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let target_dir = tmp.join("capi_target");
+    let status = Command::new(env!("CARGO"))
+        .args(["rustc", "--lib", "--features", "capi", "--crate-type", "staticlib", "--target-dir"])
+        .arg(&target_dir)
+        .status()
+        .expect("error running cargo");
+    assert!(status.success(), "error building the static library");
+    let lib = target_dir.join("debug").join("libhdt.a");
+    let out = tmp.join("capi_test");
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+        .args(["tests/capi/test.c", "-Iinclude", "-o"])
+        .arg(&out)
+        .arg(lib)
+        // native libraries needed by the Rust standard library, see `cargo rustc -- --print native-static-libs`
+        .args(["-lgcc_s", "-lutil", "-lrt", "-lpthread", "-lm", "-ldl", "-lc"])
+        .status()
+        .expect("error running the C compiler");
+    assert!(status.success(), "error compiling the C test program");
+    // the file ends with the CRC32 of the object sequence
+    let mut broken = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+    *broken.last_mut().unwrap() ^= 0xFF;
+    let broken_path = tmp.join("broken_crc.hdt");
+    std::fs::write(&broken_path, broken).unwrap();
+    let status = Command::new(&out).arg("tests/resources/snikmeta.hdt").arg(&broken_path).status().unwrap();
+    assert!(status.success(), "C test program failed");
+}
@@ -0,0 +1,61 @@
+// Test program for the C interface, compiled and run by tests/capi.rs.
+#include <stdio.h>
+#include <string.h>
+#include "hdt.h"
+
+#define CHECK(cond)                                                                \
+    if (!(cond)) {                                                                 \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+        return 1;                                                                  \
+    }
+
+int main(int argc, char **argv) {
+    CHECK(argc == 3);
+    CHECK(hdt_open("doesnotexist.hdt") == NULL);
+    CHECK(hdt_last_error() != NULL);
+    // a failed checksum panics in Rust, which must not unwind into C
+    CHECK(hdt_open(argv[2]) == NULL);
+    CHECK(strstr(hdt_last_error(), "CRC") != NULL);
+
+    HdtHandle *hdt = hdt_open(argv[1]);
+    CHECK(hdt != NULL);
+    size_t count = 1;
+    // an error is reported through the status code, not through the count
+    CHECK(hdt_count(NULL, NULL, NULL, NULL, &count) == -1);
+    CHECK(strstr(hdt_last_error(), "hdt is NULL") != NULL);
+    CHECK(count == 1);
+    CHECK(hdt_count(hdt, "\xff", NULL, NULL, &count) == -1);
+    CHECK(strstr(hdt_last_error(), "UTF-8") != NULL);
+    CHECK(hdt_count(hdt, NULL, NULL, NULL, &count) == 0);
+    CHECK(count == 327);
+    // an empty result is a success
+    CHECK(hdt_count(hdt, "doesnotexist", NULL, NULL, &count) == 0);
+    CHECK(count == 0);
+
+    const char *top = "http://www.snik.eu/ontology/meta/Top";
+    const char *label = "http://www.w3.org/2000/01/rdf-schema#label";
+    HdtIter *iter = hdt_search(hdt, top, label, NULL);
+    CHECK(iter != NULL);
+    const char *s, *p, *o;
+    CHECK(hdt_iter_next(iter, &s, &p, &o) == 1);
+    CHECK(strcmp(s, top) == 0);
+    CHECK(strcmp(p, label) == 0);
+    CHECK(strcmp(o, "\"top class\"@en") == 0);
+    CHECK(hdt_iter_next(iter, &s, &p, &o) == 0);
+    hdt_iter_free(iter);
+
+    // the iterator stays valid after the HDT is freed
+    size_t labels;
+    CHECK(hdt_count(hdt, NULL, label, NULL, &labels) == 0);
+    iter = hdt_search(hdt, NULL, label, NULL);
+    hdt_free(hdt);
+    size_t n = 0;
+    uint64_t si, pi, oi;
+    while (hdt_iter_next_ids(iter, &si, &pi, &oi) == 1) {
+        CHECK(si > 0 && pi > 0 && oi > 0);
+        n++;
+    }
+    hdt_iter_free(iter);
+    CHECK(labels > 0 && n == labels);
+    return 0;
+}
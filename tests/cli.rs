@@ -0,0 +1,52 @@
+//! Runs the hdt command line tool on the test file.
+#![cfg(feature = "cli")]
+use std::process::{Command, Output};
+
+const SNIKMETA: &str = "tests/resources/snikmeta.hdt";
+
+fn hdt(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hdt")).args(args).output().expect("error running hdt")
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = hdt(args);
+    assert!(output.status.success(), "hdt {args:?} failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn count() {
+    assert_eq!("327\n", stdout(&["count", SNIKMETA]));
+    assert_eq!("327\n", stdout(&["count", SNIKMETA, "? ? ?"]));
+    let label = "http://www.w3.org/2000/01/rdf-schema#label";
+    let labels = stdout(&["search", SNIKMETA, &format!("? <{label}> ?")]);
+    assert_eq!(format!("{}\n", labels.lines().count()), stdout(&["count", SNIKMETA, &format!("? {label} ?")]));
+}
+
+#[test]
+fn search() {
+    let top = "http://www.snik.eu/ontology/meta/Top";
+    let label = "http://www.w3.org/2000/01/rdf-schema#label";
+    assert_eq!(
+        format!("<{top}> <{label}> \"top class\"@en .\n"),
+        stdout(&["search", SNIKMETA, &format!("{top} {label} ?")])
+    );
+    // literal with a space in object position
+    assert!(stdout(&["search", SNIKMETA, "? ? \"top class\"@en"]).contains(&format!("<{top}>")));
+    assert_eq!(2, stdout(&["search", SNIKMETA, "? ? ?", "--limit", "2"]).lines().count());
+    assert_eq!("", stdout(&["search", SNIKMETA, "doesnotexist ? ?"]));
+}
+
+#[test]
+fn dump_and_info() {
+    let dump = stdout(&["dump", SNIKMETA]);
+    assert_eq!(327, dump.lines().count());
+    assert!(dump.lines().all(|line| line.ends_with(" .")));
+    let info = stdout(&["info", SNIKMETA]);
+    assert!(info.contains("327 triples"));
+    assert!(info.contains("<file://meta.ttl> <http://rdfs.org/ns/void#triples> \"327\" .\n"));
+    assert!(
+        info.contains("_:triples <http://purl.org/dc/terms/format> <http://purl.org/HDT/hdt#triplesBitmap> .\n")
+    );
+    assert!(!hdt(&["info", "doesnotexist.hdt"]).status.success());
+}
@@ -0,0 +1,52 @@
+# Run with `maturin develop --extras test && pytest tests/python` from the repository root.
+import hdt
+import pytest
+
+SNIKMETA = "tests/resources/snikmeta.hdt"
+META = "http://www.snik.eu/ontology/meta"
+TOP = "http://www.snik.eu/ontology/meta/Top"
+LABEL = "http://www.w3.org/2000/01/rdf-schema#label"
+
+
+@pytest.fixture(scope="module")
+def h():
+    return hdt.open(SNIKMETA)
+
+
+def test_open_error():
+    with pytest.raises(OSError):
+        hdt.open("doesnotexist.hdt")
+
+
+def test_search(h):
+    assert len(h) == 327
+    assert h.count() == 327
+    assert list(h.search(TOP, LABEL)) == [(TOP, LABEL, '"top class"@en')]
+    assert list(h.search("doesnotexist")) == []
+    # lazy iterator
+    triples = h.search(None, LABEL, None)
+    assert next(triples)[1] == LABEL
+    assert sum(1 for _ in triples) + 1 == h.count(p=LABEL)
+
+
+def test_search_ids(h):
+    ids = list(h.search_ids(TOP))
+    assert len(ids) == h.count(TOP)
+    for s, p, o in ids:
+        assert h.id_to_string(s, "subject") == TOP
+        assert (TOP, h.id_to_string(p, "predicate"), h.id_to_string(o, "object")) in list(h.search(TOP))
+
+
+def test_dictionary(h):
+    id = h.string_to_id(META, "subject")
+    assert id > 0
+    assert h.id_to_string(id, "subject") == META
+    assert h.string_to_id("doesnotexist", "object") == 0
+    with pytest.raises(ValueError):
+        h.string_to_id(META, "graph")
+
+
+def test_header(h):
+    header = h.header
+    assert len(header) > 0
+    assert all(len(triple) == 3 for triple in header)
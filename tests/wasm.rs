@@ -0,0 +1,33 @@
+//! Run with `wasm-pack test --node -- --features wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+use hdt::wasm::WasmHdt;
+use hdt::Hdt;
+use js_sys::Array;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// there is no file system access in WebAssembly
+const SNIKMETA: &[u8] = include_bytes!("resources/snikmeta.hdt");
+
+#[wasm_bindgen_test]
+fn load_and_query() {
+    let hdt = Hdt::from_bytes(SNIKMETA).unwrap();
+    assert_eq!(327, hdt.pattern_count(None, None, None));
+    let label = "http://www.w3.org/2000/01/rdf-schema#label";
+    assert_eq!(
+        hdt.triples_with_pattern(None, Some(label), None).count(),
+        hdt.pattern_count(None, Some(label), None)
+    );
+}
+
+#[wasm_bindgen_test]
+fn bindings() {
+    let hdt = WasmHdt::new(SNIKMETA).unwrap();
+    assert_eq!(327, hdt.pattern_count(None, None, None));
+    assert_eq!(327, hdt.triples_with_pattern(None, None, None).length());
+    let top = "http://www.snik.eu/ontology/meta/Top".to_owned();
+    let label = "http://www.w3.org/2000/01/rdf-schema#label".to_owned();
+    let labels = hdt.triples_with_pattern(Some(top), Some(label), None);
+    assert_eq!(1, labels.length());
+    let triple = Array::from(&labels.get(0));
+    assert_eq!(Some("\"top class\"@en".to_owned()), triple.get(2).as_string());
+}